@@ -2,21 +2,41 @@ extern crate itertools;
 extern crate rosalind_lib;
 
 use std::env;
-use std::fs::File;
-use std::io::BufReader;
 use std::io::BufRead;
+use std::process;
 
 use itertools::Itertools;
-use rosalind_lib::kmers::find_matches;
+use rosalind_lib::kmers::{approximate_pattern_count, find_matches};
+use rosalind_lib::io::open_file;
+
+const USAGE: &str = "usage: approximate_matching [--count] <file>";
+
+/// parse command-line arguments, not including the program name,
+/// into the input filename and whether to only count the matches
+///
+pub fn parse_args<I: Iterator<Item=String>>(args: I) -> Result<(String, bool), String> {
+    let mut filename = None;
+    let mut count_only = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--count" => count_only = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => filename = Some(arg),
+        }
+    }
+
+    let filename = filename.ok_or("Need an input filename")?;
+    Ok((filename, count_only))
+}
 
 /// read input
 ///
 pub fn read_input(filename: &str) -> (String, String, usize) {
-    let fhandle = File::open(filename)
-        .ok()
+    let fhandle = open_file(filename)
         .expect("Couldn't open file");
 
-    let mut lines = BufReader::new(fhandle).lines();
+    let mut lines = fhandle.lines();
 
     let pattern = if let Some(Ok(x)) = lines.next() {
         x
@@ -45,11 +65,25 @@ pub fn read_input(filename: &str) -> (String, String, usize) {
 }
 
 fn main() {
-    let filename = env::args().nth(1)
-        .expect("Need input filename as argument");
+    // --count prints the number of matches instead of their positions
+    let (filename, count_only) = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        },
+    };
 
     let (pattern, text, d) = read_input(&filename);
-    let res = find_matches(&pattern, &text, d);
+    if count_only {
+        let count = approximate_pattern_count(&pattern, &text, d)
+            .expect("Could not count matches");
+        println!("{}", count);
+        return;
+    }
+
+    let res = find_matches(&pattern, &text, d)
+        .expect("Could not find matches");
     let out = res.iter().join(" ");
     println!("{}", out);
 }
@@ -63,4 +97,15 @@ mod test {
         assert_eq!(res.1, "CGCCCGAATCCAGAACGCATTCCCATATTTCGGGACCACTGGCCTCCACGGTACGGACGTCAATCAAATGCCTAGCGGCTTGTGGTTTCTCCTACGCTCC".to_string());
         assert_eq!(res.2, 3);
     }
+
+    #[test]
+    fn parse_args() {
+        let parse = |args: &[&str]| super::parse_args(args.iter().map(|x| x.to_string()));
+        assert_eq!(parse(&["test.txt"]), Ok(("test.txt".to_string(), false)));
+        assert_eq!(parse(&["--count", "test.txt"]), Ok(("test.txt".to_string(), true)));
+        assert!(parse(&["test.txt", "--count"]).unwrap().1);
+        assert!(parse(&["--counts", "test.txt"]).is_err());
+        assert!(parse(&["test.txt", "--bogus"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
 }
@@ -6,46 +6,50 @@ extern crate itertools;
 use std::env;
 use itertools::Itertools;
 
-use std::fs::File;
-use std::io::BufReader;
 use std::io::BufRead;
 
-use rosalind_lib::kmers::locate_kmers;
+use rosalind_lib::kmers::{find_clumps, ClumpParams};
+use rosalind_lib::io::open_file;
 
 
-pub fn read_input(filename: &str) -> (String, usize, usize, usize){
-    let fhandle = File::open(filename)
-        .ok()
+pub fn read_input(filename: &str) -> (String, ClumpParams) {
+    let reader = open_file(filename)
         .expect("Could not read file");
-
-    let reader = BufReader::new(fhandle);
     let mut lines = reader.lines();
 
     // read the genome line
     let genome = match lines.next() {
-        Some(v) => v.ok().expect("Could not read genome line").clone(),
+        Some(v) => v.expect("Could not read genome line").clone(),
         None => panic!("No input found"),
     };
 
     // read the parameter line
     let parms = match lines.next() {
-        Some(v) => v.ok().expect("Could not read parameter line"),
+        Some(v) => v.expect("Could not read parameter line"),
         None => panic!("No parameter input line found"),
     };
 
     // parse the parameter line
     let (k, l, t) = scan_fmt!(&parms, "{} {} {}", usize, usize, usize);
-    (genome, k.unwrap(), l.unwrap(), t.unwrap())
+    let params = ClumpParams {
+        k: k.expect("Could not parse k"),
+        l: l.expect("Could not parse L"),
+        t: t.expect("Could not parse t"),
+    };
+    (genome, params)
 }
 
 fn main() {
     let filename = env::args().nth(1)
         .expect("Need an argument");
 
-    let (genome, k, l, t) = read_input(&filename);
-    let kmer_locations = locate_kmers(&genome, k);
-    let clumps = kmer_locations.find_clumps(l, t, k);
-    let res = clumps.iter().join(" ");
+    let (genome, params) = read_input(&filename);
+    let clumps = find_clumps(&genome, &params)
+        .expect("Could not find clumps");
+    let res = clumps.iter()
+        .map(|x| &x.kmer)
+        .unique()
+        .join(" ");
     println!("{}", res);
 }
 
@@ -53,10 +57,10 @@ fn main() {
 mod test {
     #[test]
     fn read_input() {
-        let (genome, k, l, t) = super::read_input("test.txt");
+        let (genome, params) = super::read_input("test.txt");
         assert_eq!(genome, "CGGACTCGACAGATGTGAAGAAATGTGAAGACTGAGTGAAGAGAAGAGGAAACACGACACGACATTGCGACATAATGTACGAATGTAATGTGCCTATGGC".to_string());
-        assert_eq!(k, 5);
-        assert_eq!(l, 75);
-        assert_eq!(t, 4);
+        assert_eq!(params.k, 5);
+        assert_eq!(params.l, 75);
+        assert_eq!(params.t, 4);
     }
 }
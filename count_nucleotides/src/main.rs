@@ -2,23 +2,21 @@ extern crate rosalind_lib;
 extern crate itertools;
 
 use std::env;
-use std::fs::File;
 use std::io::Read;
 use itertools::Itertools;
 use rosalind_lib::dna_utils::count_nucleotides;
+use rosalind_lib::io::open_file;
 
 /// Read input file
 ///
 fn read_input(filename: &str) -> String {
     // open file
-    let mut fhandle = File::open(filename)
-        .ok()
+    let mut fhandle = open_file(filename)
         .expect("Unable to open file");
 
     // allocate string to read input to
     let mut string = String::new();
     fhandle.read_to_string(&mut string)
-        .ok()
         .expect("Unable to read");
 
     // remove whitespace ('trim' returns a slice so need)
@@ -32,7 +30,8 @@ fn main() {
     let filename = env::args().nth(1)
         .expect("Need one argument");
     let string = read_input(&filename);
-    let res = count_nucleotides(&string);
+    let res = count_nucleotides(&string)
+        .expect("Could not count nucleotides");
     let res = res.iter().join(" ");
     println!("{}", &res);
 }
@@ -0,0 +1,36 @@
+extern crate rosalind_lib;
+extern crate itertools;
+
+use std::io::BufRead;
+use std::env;
+use rosalind_lib::io::open_file;
+use rosalind_lib::proteomics::cyclic_spectrum;
+use itertools::Itertools;
+
+fn read_input(filename: &str) -> String {
+    let mut lines = open_file(filename)
+            .expect("Could not open input file")
+            .lines();
+
+    if let Some(Ok(x)) = lines.next() { x.trim().to_string() } else { panic!("Could not read peptide input line") }
+}
+
+fn main() {
+    let filename = env::args().nth(1)
+        .expect("Need input filename as argument");
+    let peptide = read_input(&filename);
+    let res = cyclic_spectrum(&peptide)
+                .expect("Could not compute spectrum")
+                .iter()
+                .join(" ");
+    println!("{}", res);
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn read_input() {
+        let res = super::read_input("test.txt");
+        assert_eq!(res, "LEQN");
+    }
+}
@@ -0,0 +1,36 @@
+extern crate itertools;
+extern crate rosalind_lib;
+
+use itertools::Itertools;
+use std::env;
+use rosalind_lib::io::FastaReader;
+use rosalind_lib::matching::prefix_function;
+
+/// read the first record of a FASTA file
+///
+pub fn read_input(filename: &str) -> String {
+    let mut reader = FastaReader::from_file(filename)
+        .expect("Could not open file");
+    let record = reader.next()
+        .expect("No FASTA record found")
+        .expect("Could not read record");
+    record.sequence
+}
+
+fn main() {
+    let filename = env::args().nth(1)
+        .expect("Need one argument");
+
+    let dna = read_input(&filename);
+    let out = prefix_function(dna.as_bytes()).iter().join(" ");
+    println!("{}", out);
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn read_input() {
+        let dna = super::read_input("test.txt");
+        assert_eq!(dna, "CAGCATGGTATCACAGCAGAG");
+    }
+}
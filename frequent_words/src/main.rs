@@ -2,20 +2,66 @@ extern crate rosalind_lib;
 extern crate itertools;
 
 use rosalind_lib::kmers::count_kmers;
+use rosalind_lib::io::open_file;
 
 use std::env;
+use std::process;
 use itertools::Itertools;
 
-use std::fs::File;
-use std::io::BufReader;
 use std::io::BufRead;
 
+const USAGE: &str = "usage: frequent_words [--top N | --min-count C | --histogram] [--counts] <file>";
+
+/// which kmers to report
+///
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    MostFrequent,
+    Top(usize),
+    MinCount(i32),
+    Histogram,
+}
+
+/// command-line options
+///
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub filename: String,
+    pub query: Query,
+    pub counts: bool,
+}
+
+/// parse command-line arguments, not including the program name
+///
+pub fn parse_args<I: Iterator<Item=String>>(mut args: I) -> Result<Options, String> {
+    let mut filename = None;
+    let mut query = Query::MostFrequent;
+    let mut counts = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--top" => {
+                let n = args.next().ok_or("--top needs a value")?;
+                query = Query::Top(n.parse().map_err(|_| format!("Could not parse --top value {}", n))?);
+            },
+            "--min-count" => {
+                let c = args.next().ok_or("--min-count needs a value")?;
+                query = Query::MinCount(c.parse().map_err(|_| format!("Could not parse --min-count value {}", c))?);
+            },
+            "--histogram" => query = Query::Histogram,
+            "--counts" => counts = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => filename = Some(arg),
+        }
+    }
+
+    let filename = filename.ok_or("Need an input filename")?;
+    Ok(Options { filename, query, counts })
+}
 
 pub fn read_input(filename: &str) -> (String, usize) {
-    let fhandle = File::open(filename)
-        .ok()
+    let reader = open_file(filename)
         .expect("Could not open file");
-    let reader = BufReader::new(fhandle);
     let lines: Vec<String> = reader.lines().map(|x| x.unwrap()).collect();
     let dna: String = lines[0].clone();
     let k: usize = lines[1].parse().unwrap();
@@ -23,22 +69,73 @@ pub fn read_input(filename: &str) -> (String, usize) {
 }
 
 fn main() {
-    let filename = env::args().nth(1)
-        .expect("Need an argument");
-
-    let (dna, k) = read_input(&filename);
-    let kmer_counts = count_kmers(&dna, k);
-    let frequent_kmers: Vec<String> = kmer_counts.find_frequent_kmers();
-    let res = frequent_kmers.iter().join(" ");
-    println!("{}", res);
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        },
+    };
+
+    let (dna, k) = read_input(&options.filename);
+    let kmer_counts = count_kmers(&dna, k)
+        .expect("Could not count kmers");
+
+    let kmers: Vec<(String, i32)> = match options.query {
+        Query::MostFrequent => {
+            // every most frequent kmer shares the highest count
+            let highest_count = kmer_counts.top_n(1).first().map_or(0, |x| x.1);
+            kmer_counts.find_frequent_kmers().into_iter()
+                .map(|kmer| (kmer, highest_count))
+                .collect()
+        },
+        Query::Top(n) => kmer_counts.top_n(n).into_iter()
+            .map(|(kmer, count)| (kmer.to_string(), count))
+            .collect(),
+        Query::MinCount(c) => kmer_counts.with_min_count(c).into_iter()
+            .map(|(kmer, count)| (kmer.to_string(), count))
+            .collect(),
+        Query::Histogram => {
+            for (count, kmers) in kmer_counts.histogram() {
+                println!("{}\t{}", count, kmers);
+            }
+            return;
+        },
+    };
+
+    if options.counts {
+        for (kmer, count) in kmers {
+            println!("{}\t{}", kmer, count);
+        }
+    } else {
+        let res = kmers.iter().map(|x| &x.0).join(" ");
+        println!("{}", res);
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::{Options, Query};
+
     #[test]
     fn read_input() {
         let (dna, k) = super::read_input("test.txt");
         assert_eq!(dna, "ACGTTGCATGTCGCATGATGCATGAGAGCT");
         assert_eq!(k, 4);
     }
+
+    #[test]
+    fn parse_args() {
+        let parse = |args: &[&str]| super::parse_args(args.iter().map(|x| x.to_string()));
+        assert_eq!(parse(&["test.txt"]),
+                   Ok(Options { filename: "test.txt".to_string(), query: Query::MostFrequent, counts: false }));
+        assert_eq!(parse(&["--top", "5", "--counts", "test.txt"]),
+                   Ok(Options { filename: "test.txt".to_string(), query: Query::Top(5), counts: true }));
+        assert_eq!(parse(&["test.txt", "--min-count", "2"]).unwrap().query, Query::MinCount(2));
+        assert_eq!(parse(&["--histogram", "test.txt"]).unwrap().query, Query::Histogram);
+        assert!(parse(&["--top", "x", "test.txt"]).is_err());
+        assert!(parse(&["--top"]).is_err());
+        assert!(parse(&["--bogus", "test.txt"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
 }
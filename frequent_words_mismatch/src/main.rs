@@ -5,18 +5,16 @@ use std::env;
 
 use itertools::Itertools;
 use rosalind_lib::kmers::count_mismatch_kmers;
-use std::fs::File;
-use std::io::BufReader;
+use rosalind_lib::io::open_file;
 use std::io::BufRead;
 
 /// read input
 ///
 pub fn read_input(filename: &str) -> (String, usize, usize) {
-    let fhandle = File::open(filename)
-        .ok()
+    let fhandle = open_file(filename)
         .expect("Couldn't open file");
 
-    let mut lines = BufReader::new(fhandle).lines();
+    let mut lines = fhandle.lines();
 
     let text = if let Some(Ok(x)) = lines.next() { x } else { panic!("Could not read text"); };
     let kdstr = if let Some(Ok(x)) = lines.next() { x } else { panic!("Could not read d"); };
@@ -33,7 +31,8 @@ fn main() {
         .expect("Need input filename as argument");
 
     let (text, k, d) = read_input(&filename);
-    let kmer_counts = count_mismatch_kmers(&text, k, d);
+    let kmer_counts = count_mismatch_kmers(&text, k, d)
+        .expect("Could not count kmers");
     let frequent_kmers: Vec<String> = kmer_counts.find_frequent_kmers();
     let res = frequent_kmers.iter().join(" ");
     println!("{}", res);
@@ -4,20 +4,17 @@ extern crate rosalind_lib;
 use std::env;
 
 use itertools::Itertools;
-use rosalind_lib::kmers::count_mismatch_kmers;
-use rosalind_lib::dna_utils::revcomp;
+use rosalind_lib::kmers::{count_mismatch_kmers_canonical, Kmer};
+use rosalind_lib::io::open_file;
 
-use std::fs::File;
-use std::io::BufReader;
 use std::io::BufRead;
 
 
 pub fn read_input(filename: &str) -> (String, usize, usize) {
-    let fhandle = File::open(filename)
-        .ok()
+    let fhandle = open_file(filename)
         .expect("Couldn't open file");
 
-    let mut lines = BufReader::new(fhandle).lines();
+    let mut lines = fhandle.lines();
 
     let text = if let Some(Ok(x)) = lines.next() { x } else { panic!("Could not read text"); };
     let kdstr = if let Some(Ok(x)) = lines.next() { x } else { panic!("Could not read d"); };
@@ -34,15 +31,27 @@ fn main() {
         .expect("Need input filename as argument");
 
     let (text, k, d) = read_input(&filename);
-    let mut kmer_counts = count_mismatch_kmers(&text, k, d);
-    let revcomp_text = revcomp(&text);
-    let revcomp_kmer_counts = count_mismatch_kmers(&revcomp_text, k, d);
+    let kmer_counts = count_mismatch_kmers_canonical(&text, k, d)
+        .expect("Could not count kmers");
 
-    for (kmer, count) in revcomp_kmer_counts.to_hashmap() {
-        kmer_counts.insert_u8(kmer, *count);
-    }
+    // a canonical count covers a kmer and its reverse complement,
+    // palindromes are their own reverse complement so count twice
+    let strand_count = |kmer: &Kmer, count: i32| {
+        if kmer.revcomp() == *kmer { 2 * count } else { count }
+    };
+    let highest_count = kmer_counts.to_hashmap().iter()
+        .map(|(kmer, count)| strand_count(kmer, *count))
+        .max()
+        .unwrap_or(0);
 
-    let frequent_kmers: Vec<String> = kmer_counts.find_frequent_kmers();
+    let mut frequent_kmers: Vec<String> = Vec::new();
+    for (kmer, count) in kmer_counts.to_hashmap() {
+        if strand_count(kmer, *count) < highest_count { continue; }
+        frequent_kmers.push(kmer.to_string());
+        if kmer.revcomp() != *kmer {
+            frequent_kmers.push(kmer.revcomp().to_string());
+        }
+    }
     let res = frequent_kmers.iter().join(" ");
     println!("{}", res);
 }
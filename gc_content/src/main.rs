@@ -1,22 +1,26 @@
 extern crate rosalind_lib;
 
 use std::env;
-use rosalind_lib::io::parse_fasta_file;
+use rosalind_lib::io::FastaReader;
 use rosalind_lib::dna_utils::gc_content;
 
 pub fn main() {
     let filename = env::args().nth(1)
         .expect("Need one argument");
 
-    let records = parse_fasta_file(&filename);
-    let mut maxgc = -1.0 as f32;
-    let mut maxid = "";
+    let reader = FastaReader::from_file(&filename)
+        .expect("Could not open file");
+    let mut maxgc = -1.0f32;
+    let mut maxid = String::new();
 
-    for (id, dna) in &records {
-        let curgc = gc_content(&dna);
+    // records come in file order, so ties go to the first record seen
+    for record in reader {
+        let record = record.expect("Could not read record");
+        let curgc = gc_content(&record.sequence)
+            .expect("Could not compute GC content");
         if curgc > maxgc {
             maxgc = curgc;
-            maxid = id;
+            maxid = record.id;
         }
     }
     println!("{}", maxid);
@@ -0,0 +1,46 @@
+extern crate rosalind_lib;
+
+use std::env;
+use rosalind_lib::io::FastaReader;
+use rosalind_lib::orf::{find_orfs, OrfOptions};
+
+/// read the first record of a FASTA file
+///
+pub fn read_input(filename: &str) -> String {
+    let mut reader = FastaReader::from_file(filename)
+        .expect("Could not open file");
+    let record = reader.next()
+        .expect("No FASTA record found")
+        .expect("Could not read record");
+    record.sequence
+}
+
+fn main() {
+    let filename = env::args().nth(1)
+        .expect("Need one argument");
+
+    let dna = read_input(&filename);
+    let options = OrfOptions { nested: true, ..Default::default() };
+    let orfs = find_orfs(&dna, &options)
+        .expect("Could not find ORFs");
+
+    // print each distinct protein once, in order of position
+    let mut proteins: Vec<&str> = Vec::new();
+    for orf in &orfs {
+        if !proteins.contains(&orf.protein.as_str()) {
+            proteins.push(&orf.protein);
+        }
+    }
+    for protein in proteins {
+        println!("{}", protein);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn read_input() {
+        let dna = super::read_input("test.txt");
+        assert_eq!(dna, "AGCCATGTAGCTAACTCAGGTTACATGGGGATGACCCCGCGACTTGGATTAGAGTCTCTTTTGGAATAAGCCTGAATGATCCGAGTAGCATCTCAG");
+    }
+}
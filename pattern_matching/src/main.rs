@@ -3,32 +3,77 @@ extern crate rosalind_lib;
 
 use itertools::Itertools;
 use std::env;
-use std::fs::File;
-use std::io::BufReader;
+use std::process;
 use std::io::BufRead;
 
-use rosalind_lib::matching::naive;
+use rosalind_lib::matching::{kmp, naive, z_algorithm};
+use rosalind_lib::io::open_file;
+use rosalind_lib::Result;
+
+const USAGE: &str = "usage: pattern_matching [--algorithm naive|kmp|z] <file>";
+
+/// exact matching algorithm to use
+///
+#[derive(Debug, PartialEq)]
+pub enum Algorithm {
+    Naive,
+    Kmp,
+    Z,
+}
+
+impl Algorithm {
+    fn run(&self, pattern: &str, genome: &str) -> Result<Vec<usize>> {
+        match *self {
+            Algorithm::Naive => naive(pattern, genome),
+            Algorithm::Kmp => kmp(pattern, genome),
+            Algorithm::Z => z_algorithm(pattern, genome),
+        }
+    }
+}
+
+/// parse command-line arguments, not including the program name,
+/// into the input filename and the algorithm (KMP by default)
+///
+pub fn parse_args<I: Iterator<Item=String>>(mut args: I) -> ::std::result::Result<(String, Algorithm), String> {
+    let mut filename = None;
+    let mut algorithm = Algorithm::Kmp;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => {
+                let name = args.next().ok_or("--algorithm needs a value")?;
+                algorithm = match name.as_str() {
+                    "naive" => Algorithm::Naive,
+                    "kmp" => Algorithm::Kmp,
+                    "z" => Algorithm::Z,
+                    _ => return Err(format!("Unknown algorithm {}", name)),
+                };
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => filename = Some(arg),
+        }
+    }
+
+    let filename = filename.ok_or("Need an input filename")?;
+    Ok((filename, algorithm))
+}
 
 pub fn read_input(filename: &str) -> (String, String) {
     // open file and get lines iterator
-    let fhandle = File::open(filename)
-                    .ok()
+    let reader = open_file(filename)
                     .expect("Could not open file");
-    let reader  = BufReader::new(fhandle);
     let mut lines = reader.lines();
 
     // read the pattern line
     let pattern: String = match lines.next() {
-        Some(v) => v.ok()
-                    .expect("Could not read pattern input line")
+        Some(v) => v.expect("Could not read pattern input line")
                     .clone(),
         None => panic!("No input pattern line found"),
     };
 
     // read the genome line
     let genome: String = match lines.next() {
-        Some(v) => v.ok()
-                    .expect("Could not read genome input line")
+        Some(v) => v.expect("Could not read genome input line")
                     .clone(),
         None => panic!("No input genome line found"),
     };
@@ -38,20 +83,39 @@ pub fn read_input(filename: &str) -> (String, String) {
 }
 
 fn main() {
-    let filename = env::args().nth(1)
-        .expect("Need filename as argument");
+    let (filename, algorithm) = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        },
+    };
     let (pattern, genome) = read_input(&filename);
-    let res = naive(&pattern, &genome);
+    let res = algorithm.run(&pattern, &genome)
+        .expect("Could not match pattern");
     let out = res.iter().join(" ");
     println!("{}", out);
 }
 
 #[cfg(test)]
 mod test {
+    use super::Algorithm;
+
     #[test]
     fn read_input() {
         let (pattern, genome) = super::read_input("test.txt");
         assert_eq!(pattern, "ATAT".to_string());
         assert_eq!(genome, "GATATATGCATATACTT".to_string());
     }
+
+    #[test]
+    fn parse_args() {
+        let parse = |args: &[&str]| super::parse_args(args.iter().map(|x| x.to_string()));
+        assert_eq!(parse(&["test.txt"]), Ok(("test.txt".to_string(), Algorithm::Kmp)));
+        assert_eq!(parse(&["--algorithm", "naive", "test.txt"]).unwrap().1, Algorithm::Naive);
+        assert_eq!(parse(&["test.txt", "--algorithm", "z"]).unwrap().1, Algorithm::Z);
+        assert!(parse(&["--algorithm", "boyer-moore", "test.txt"]).is_err());
+        assert!(parse(&["--algorithm"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
 }
@@ -0,0 +1,41 @@
+extern crate rosalind_lib;
+
+use std::env;
+
+use std::io::Read;
+use rosalind_lib::io::open_file;
+use rosalind_lib::proteomics::peptide_mass;
+
+/// Read problem input from file
+///
+pub fn read_input(filename: &str) -> String {
+    let mut fhandle = open_file(filename)
+        .expect("Could not open file");
+
+    let mut protein = String::new();
+    fhandle.read_to_string(&mut protein)
+        .expect("Unable to read");
+
+    protein.trim().to_string()
+}
+
+fn main() {
+    let filename = env::args().nth(1)
+        .expect("Need one argument");
+
+    let protein = read_input(&filename);
+    let res = peptide_mass(&protein)
+        .expect("Could not compute protein mass");
+    println!("{:.3}", res);
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn read_input() {
+        let filename = "test.txt";
+        let protein = "SKADYEK";
+        let res = super::read_input(filename);
+        assert_eq!(res, protein);
+    }
+}
@@ -2,19 +2,17 @@ extern crate rosalind_lib;
 
 use std::env;
 use rosalind_lib::dna_utils::revcomp;
-use std::fs::File;
+use rosalind_lib::io::open_file;
 use std::io::Read;
 
 /// read string from file
 ///
 pub fn read_input(filename: &str) -> String {
-    let mut fhandle = File::open(filename)
-        .ok()
+    let mut fhandle = open_file(filename)
         .expect("Could not open file");
 
     let mut string = String::new();
     fhandle.read_to_string(&mut string)
-        .ok()
         .expect("Could not read file");
     string.trim().to_string()
 }
@@ -22,7 +20,9 @@ pub fn read_input(filename: &str) -> String {
 fn main() {
     let filename = env::args().nth(1)
         .expect("Need one input");
-    let res = revcomp(&filename);
+    let string = read_input(&filename);
+    let res = revcomp(&string)
+        .expect("Could not reverse complement");
     println!("{}", res);
 }
 
@@ -31,7 +31,7 @@ mod test {
     #[test]
     fn read_input() {
         let filename = "test.txt";
-        let res = super::read_input(&filename);
+        let res = super::read_input(filename);
         assert_eq!(res, "AAAACCCGGT");
     }
 }
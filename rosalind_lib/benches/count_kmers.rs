@@ -0,0 +1,54 @@
+extern crate rosalind_lib;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use rosalind_lib::kmers::count_kmers_threaded;
+use rosalind_lib::parallel::default_threads;
+
+/// pseudo-random DNA sequence from a linear congruential generator
+///
+fn random_dna(n: usize) -> String {
+    let mut state: u64 = 42;
+    (0..n).map(|_| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        b"ACGT"[(state >> 62) as usize] as char
+    }).collect()
+}
+
+/// serial reference implementation
+///
+fn count_kmers_serial(dna: &str, k: usize) -> HashMap<&[u8], i32> {
+    let mut counts = HashMap::new();
+    for kmer in dna.as_bytes().windows(k) {
+        *counts.entry(kmer).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// best wall-clock time over a few runs
+///
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    (0..5).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+fn main() {
+    let dna = random_dna(2_000_000);
+    let k = 12;
+
+    let serial = time(|| { count_kmers_serial(&dna, k); });
+    println!("{:<24} {:>10.2?}", "serial", serial);
+
+    let mut threads = 1;
+    while threads <= default_threads() {
+        let elapsed = time(|| {
+            count_kmers_threaded(&dna, k, threads).expect("Could not count kmers");
+        });
+        println!("{:<24} {:>10.2?}", format!("count_kmers ({} threads)", threads), elapsed);
+        threads *= 2;
+    }
+}
@@ -0,0 +1,491 @@
+/// rearrange a slice into the next permutation in lexicographic
+/// order, returns false if it was already the last one
+///
+fn next_permutation(vals: &mut [usize]) -> bool {
+    let n = vals.len();
+    if n < 2 { return false; }
+
+    // find the last ascent, everything after it is descending
+    let mut i = n - 1;
+    while i > 0 && vals[i - 1] >= vals[i] { i -= 1; }
+    if i == 0 { return false; }
+
+    // swap in the smallest larger value from the tail
+    let mut j = n - 1;
+    while vals[j] <= vals[i - 1] { j -= 1; }
+    vals.swap(i - 1, j);
+    vals[i..].reverse();
+    true
+}
+
+/// cartesian power of 0..n, every vector of length d with values
+/// in 0..n, in lexicographic order
+///
+#[derive(Debug, Clone)]
+pub struct Product {
+    n: usize,
+    d: usize,
+    vals: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl Product {
+    pub fn new(n: usize, d: usize) -> Product {
+        Product { n, d, vals: Vec::with_capacity(d), started: false, done: false }
+    }
+
+    /// move to the next vector, returns false when there are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if self.done { return false; }
+        if !self.started {
+            self.started = true;
+            // there are no vectors with values in an empty range
+            if self.n == 0 && self.d > 0 {
+                self.done = true;
+                return false;
+            }
+            self.vals.resize(self.d, 0);
+            return true;
+        }
+
+        for i in (0..self.d).rev() {
+            if self.vals[i] + 1 < self.n {
+                self.vals[i] += 1;
+                for val in &mut self.vals[i+1..] { *val = 0; }
+                return true;
+            }
+        }
+        self.done = true;
+        false
+    }
+
+    /// the current vector
+    ///
+    pub fn current(&self) -> &[usize] {
+        &self.vals
+    }
+}
+
+impl Iterator for Product {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.vals.clone()) } else { None }
+    }
+}
+
+/// combinations of d values from 0..n, each as an increasing vector,
+/// in lexicographic order
+///
+#[derive(Debug, Clone)]
+pub struct Combinations {
+    n: usize,
+    d: usize,
+    vals: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl Combinations {
+    pub fn new(n: usize, d: usize) -> Combinations {
+        Combinations { n, d, vals: Vec::with_capacity(d), started: false, done: false }
+    }
+
+    /// move to the next combination, returns false when there are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if self.done { return false; }
+        if !self.started {
+            self.started = true;
+            // there are no combinations if d > n
+            if self.d > self.n {
+                self.done = true;
+                return false;
+            }
+            self.vals.extend(0..self.d);
+            return true;
+        }
+
+        // advance the last position that still has room
+        for i in (0..self.d).rev() {
+            if self.vals[i] + (self.d - i) < self.n {
+                self.vals[i] += 1;
+                for j in i+1..self.d { self.vals[j] = self.vals[j - 1] + 1; }
+                return true;
+            }
+        }
+        self.done = true;
+        false
+    }
+
+    /// the current combination
+    ///
+    pub fn current(&self) -> &[usize] {
+        &self.vals
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.vals.clone()) } else { None }
+    }
+}
+
+/// permutations of 0..n in lexicographic order
+///
+#[derive(Debug, Clone)]
+pub struct Permutations {
+    vals: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl Permutations {
+    pub fn new(n: usize) -> Permutations {
+        Permutations { vals: (0..n).collect(), started: false, done: false }
+    }
+
+    /// move to the next permutation, returns false when there are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if self.done { return false; }
+        if !self.started {
+            self.started = true;
+            return true;
+        }
+        self.done = !next_permutation(&mut self.vals);
+        !self.done
+    }
+
+    /// the current permutation
+    ///
+    pub fn current(&self) -> &[usize] {
+        &self.vals
+    }
+}
+
+impl Iterator for Permutations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.vals.clone()) } else { None }
+    }
+}
+
+/// partial permutations, ordered selections of k values from 0..n,
+/// in lexicographic order
+///
+#[derive(Debug, Clone)]
+pub struct KPermutations {
+    k: usize,
+    vals: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl KPermutations {
+    pub fn new(n: usize, k: usize) -> KPermutations {
+        KPermutations { k, vals: (0..n).collect(), started: false, done: k > n }
+    }
+
+    /// move to the next partial permutation, returns false when there
+    /// are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if self.done { return false; }
+        if !self.started {
+            self.started = true;
+            return true;
+        }
+        // the values after the first k are kept increasing, reversing
+        // them jumps to the last permutation with the current prefix
+        self.vals[self.k..].reverse();
+        self.done = !next_permutation(&mut self.vals);
+        !self.done
+    }
+
+    /// the current partial permutation
+    ///
+    pub fn current(&self) -> &[usize] {
+        &self.vals[..self.k]
+    }
+}
+
+impl Iterator for KPermutations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.current().to_vec()) } else { None }
+    }
+}
+
+/// signed permutations of 1..=n, every permutation with every
+/// assignment of signs, positive signs first
+///
+#[derive(Debug, Clone)]
+pub struct SignedPermutations {
+    permutations: Permutations,
+    signs: Product,
+    vals: Vec<isize>,
+}
+
+impl SignedPermutations {
+    pub fn new(n: usize) -> SignedPermutations {
+        let mut permutations = Permutations::new(n);
+        permutations.advance();
+        SignedPermutations { permutations, signs: Product::new(2, n), vals: Vec::with_capacity(n) }
+    }
+
+    /// move to the next signed permutation, returns false when there
+    /// are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if !self.signs.advance() {
+            if !self.permutations.advance() { return false; }
+            let n = self.permutations.current().len();
+            self.signs = Product::new(2, n);
+            self.signs.advance();
+        }
+
+        self.vals.clear();
+        let values = self.permutations.current().iter().zip(self.signs.current());
+        self.vals.extend(values.map(|(&x, &sign)| {
+            let x = x as isize + 1;
+            if sign == 0 { x } else { -x }
+        }));
+        true
+    }
+
+    /// the current signed permutation
+    ///
+    pub fn current(&self) -> &[isize] {
+        &self.vals
+    }
+}
+
+impl Iterator for SignedPermutations {
+    type Item = Vec<isize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.vals.clone()) } else { None }
+    }
+}
+
+/// every string of length k over an alphabet, in lexicographic order
+/// given by the order of the alphabet
+///
+#[derive(Debug, Clone)]
+pub struct Lexicographic<'a, T: 'a> {
+    alphabet: &'a [T],
+    indices: Product,
+    vals: Vec<T>,
+}
+
+impl<'a, T: Clone> Lexicographic<'a, T> {
+    pub fn new(alphabet: &'a [T], k: usize) -> Lexicographic<'a, T> {
+        Lexicographic { alphabet, indices: Product::new(alphabet.len(), k), vals: Vec::with_capacity(k) }
+    }
+
+    /// move to the next string, returns false when there are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if !self.indices.advance() { return false; }
+        self.vals.clear();
+        let alphabet = self.alphabet;
+        self.vals.extend(self.indices.current().iter().map(|&i| alphabet[i].clone()));
+        true
+    }
+
+    /// the current string
+    ///
+    pub fn current(&self) -> &[T] {
+        &self.vals
+    }
+}
+
+impl<'a, T: Clone> Iterator for Lexicographic<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.vals.clone()) } else { None }
+    }
+}
+
+/// every string of length 1 to k over an alphabet in dictionary
+/// order, where a string comes right before its extensions (as in
+/// the LEXV problem)
+///
+#[derive(Debug, Clone)]
+pub struct Lexv<'a, T: 'a> {
+    alphabet: &'a [T],
+    k: usize,
+    indices: Vec<usize>,
+    vals: Vec<T>,
+    started: bool,
+    done: bool,
+}
+
+impl<'a, T: Clone> Lexv<'a, T> {
+    pub fn new(alphabet: &'a [T], k: usize) -> Lexv<'a, T> {
+        Lexv {
+            alphabet, k,
+            indices: Vec::with_capacity(k),
+            vals: Vec::with_capacity(k),
+            started: false,
+            done: alphabet.is_empty() || k == 0,
+        }
+    }
+
+    /// move to the next string, returns false when there are no more
+    ///
+    pub fn advance(&mut self) -> bool {
+        if !self.advance_indices() { return false; }
+        self.vals.clear();
+        let alphabet = self.alphabet;
+        self.vals.extend(self.indices.iter().map(|&i| alphabet[i].clone()));
+        true
+    }
+
+    /// the current string
+    ///
+    pub fn current(&self) -> &[T] {
+        &self.vals
+    }
+
+    fn advance_indices(&mut self) -> bool {
+        if self.done { return false; }
+        let n = self.alphabet.len();
+        if !self.started {
+            self.started = true;
+            self.indices.push(0);
+            return true;
+        }
+
+        // extend the string before moving on to its siblings
+        if self.indices.len() < self.k {
+            self.indices.push(0);
+            return true;
+        }
+        while let Some(last) = self.indices.pop() {
+            if last + 1 < n {
+                self.indices.push(last + 1);
+                return true;
+            }
+        }
+        self.done = true;
+        false
+    }
+}
+
+impl<'a, T: Clone> Iterator for Lexv<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() { Some(self.vals.clone()) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn product() {
+        let prod: Vec<_> = super::Product::new(3,2).collect();
+        assert_eq!(prod, vec![[0,0],[0,1],[0,2],[1,0],[1,1],[1,2],[2,0],[2,1],[2,2]]);
+        assert_eq!(super::Product::new(3, 0).count(), 1);
+        assert_eq!(super::Product::new(0, 2).count(), 0);
+    }
+
+    #[test]
+    fn combinations() {
+        let combs: Vec<_> = super::Combinations::new(3, 2).collect();
+        assert_eq!(combs, vec![[0,1], [0,2], [1,2]]);
+    }
+
+    #[test]
+    fn combinations_edge_cases() {
+        let combs: Vec<_> = super::Combinations::new(2, 2).collect();
+        assert_eq!(combs, vec![[0,1]]);
+        assert_eq!(super::Combinations::new(2, 0).count(), 1);
+        assert_eq!(super::Combinations::new(2, 3).count(), 0);
+    }
+
+    #[test]
+    fn advance_without_allocating() {
+        let mut combinations = super::Combinations::new(4, 2);
+        let mut sums = Vec::new();
+        while combinations.advance() {
+            sums.push(combinations.current().iter().sum::<usize>());
+        }
+        assert_eq!(sums, vec![1, 2, 3, 3, 4, 5]);
+        assert!(!combinations.advance());
+    }
+
+    #[test]
+    fn permutations() {
+        let perms: Vec<_> = super::Permutations::new(3).collect();
+        assert_eq!(perms, vec![[0,1,2], [0,2,1], [1,0,2], [1,2,0], [2,0,1], [2,1,0]]);
+        assert_eq!(super::Permutations::new(0).count(), 1);
+        assert_eq!(super::Permutations::new(5).count(), 120);
+    }
+
+    #[test]
+    fn k_permutations() {
+        let perms: Vec<_> = super::KPermutations::new(3, 2).collect();
+        assert_eq!(perms, vec![[0,1], [0,2], [1,0], [1,2], [2,0], [2,1]]);
+        // 7!/(7-3)!
+        assert_eq!(super::KPermutations::new(7, 3).count(), 210);
+        assert_eq!(super::KPermutations::new(4, 4).count(), 24);
+        assert_eq!(super::KPermutations::new(4, 0).count(), 1);
+        assert_eq!(super::KPermutations::new(2, 3).count(), 0);
+    }
+
+    #[test]
+    fn signed_permutations() {
+        let perms: Vec<_> = super::SignedPermutations::new(2).collect();
+        assert_eq!(perms, vec![[1,2], [1,-2], [-1,2], [-1,-2],
+                               [2,1], [2,-1], [-2,1], [-2,-1]]);
+        assert_eq!(super::SignedPermutations::new(3).count(), 48);
+    }
+
+    #[test]
+    fn lexicographic() {
+        let strings: Vec<String> = super::Lexicographic::new(&['A', 'C', 'G', 'T'], 2)
+            .map(|x| x.into_iter().collect())
+            .collect();
+        assert_eq!(strings.len(), 16);
+        assert_eq!(strings[..5], ["AA", "AC", "AG", "AT", "CA"]);
+        assert_eq!(strings[15], "TT");
+
+        let mut lexicographic = super::Lexicographic::new(b"01", 3);
+        let mut count = 0;
+        while lexicographic.advance() {
+            count += 1;
+            assert_eq!(lexicographic.current().len(), 3);
+        }
+        assert_eq!(count, 8);
+    }
+
+    #[test]
+    fn lexv() {
+        let strings: Vec<String> = super::Lexv::new(&['D', 'N', 'A'], 3)
+            .map(|x| x.into_iter().collect())
+            .collect();
+        // 3 + 9 + 27 strings
+        assert_eq!(strings.len(), 39);
+        assert_eq!(strings[..8], ["D", "DD", "DDD", "DDN", "DDA", "DN", "DND", "DNN"]);
+        assert_eq!(strings[38], "AAA");
+        assert_eq!(super::Lexv::new(&['A'], 0).count(), 0);
+
+        let mut lexv = super::Lexv::new(b"AB", 2);
+        let mut strings = Vec::new();
+        while lexv.advance() {
+            strings.push(String::from_utf8(lexv.current().to_vec()).unwrap());
+        }
+        assert_eq!(strings, ["A", "AA", "AB", "B", "BA", "BB"]);
+        assert!(!lexv.advance());
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use error::{Error, Result};
+use parallel::{default_threads, map_ranges};
+use proteomics::{cyclic_spectrum_masses, linear_spectrum_masses, INTEGER_MASSES};
+
+/// number of shared masses between two sorted spectra, counting
+/// repeated masses as many times as they appear in both
+///
+fn shared_masses(left: &[u32], right: &[u32]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            count += 1;
+            i += 1;
+            j += 1;
+        } else if left[i] < right[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    count
+}
+
+/// score a linear peptide against a sorted experimental spectrum
+///
+pub fn linear_score(peptide: &[u32], spectrum: &[u32]) -> usize {
+    shared_masses(&linear_spectrum_masses(peptide), spectrum)
+}
+
+/// score a cyclic peptide against a sorted experimental spectrum
+///
+pub fn cyclic_score(peptide: &[u32], spectrum: &[u32]) -> usize {
+    shared_masses(&cyclic_spectrum_masses(peptide), spectrum)
+}
+
+/// sort a spectrum and find its parent mass
+///
+fn prepare_spectrum(spectrum: &[u32]) -> Result<(Vec<u32>, u32)> {
+    let mut spectrum = spectrum.to_vec();
+    spectrum.sort();
+    match spectrum.last() {
+        Some(&parent) => Ok((spectrum, parent)),
+        None => Err(Error::EmptySequence),
+    }
+}
+
+/// add every mass in the alphabet to the end of every peptide
+///
+fn expand(peptides: &[Vec<u32>], alphabet: &[u32]) -> Vec<Vec<u32>> {
+    let mut expanded = Vec::with_capacity(peptides.len() * alphabet.len());
+    for peptide in peptides {
+        for &mass in alphabet {
+            let mut next = peptide.clone();
+            next.push(mass);
+            expanded.push(next);
+        }
+    }
+    expanded
+}
+
+/// exact cyclopeptide sequencing from an ideal spectrum by branch
+/// and bound, returns every peptide, as a list of amino acid masses,
+/// whose cyclic spectrum is the input spectrum
+///
+pub fn cyclopeptide_sequencing(spectrum: &[u32]) -> Result<Vec<Vec<u32>>> {
+    let (spectrum, parent) = prepare_spectrum(spectrum)?;
+    let mut peptides: Vec<Vec<u32>> = vec![Vec::new()];
+    let mut res = Vec::new();
+
+    while !peptides.is_empty() {
+        peptides = expand(&peptides, &INTEGER_MASSES);
+
+        // bound: keep peptides whose linear spectrum fits the spectrum
+        peptides.retain(|peptide| {
+            let mass: u32 = peptide.iter().sum();
+            if mass == parent {
+                if cyclic_spectrum_masses(peptide) == spectrum {
+                    res.push(peptide.clone());
+                }
+                return false;
+            }
+            let linear = linear_spectrum_masses(peptide);
+            shared_masses(&linear, &spectrum) == linear.len()
+        });
+    }
+    Ok(res)
+}
+
+/// options for leaderboard cyclopeptide sequencing
+///
+/// `leaderboard_size` is the number of top scoring peptides kept
+/// each round (ties with the last one are kept too) and `threads`
+/// is the number of threads used to score candidate peptides
+///
+#[derive(Debug, Clone)]
+pub struct LeaderboardOptions {
+    pub leaderboard_size: usize,
+    pub threads: usize,
+}
+
+impl Default for LeaderboardOptions {
+    fn default() -> Self {
+        LeaderboardOptions {
+            leaderboard_size: 1000,
+            threads: default_threads(),
+        }
+    }
+}
+
+/// score peptides on a bounded number of threads, each thread
+/// scores one contiguous chunk of the peptides
+///
+fn parallel_scores<F>(peptides: &[Vec<u32>], threads: usize, score: F) -> Result<Vec<usize>>
+    where F: Fn(&[u32]) -> usize + Sync
+{
+    let chunks = map_ranges(peptides.len(), threads, |range| {
+        peptides[range].iter().map(|x| score(x)).collect::<Vec<_>>()
+    })?;
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+/// keep the top scoring peptides, including ties at the cutoff
+///
+fn trim(peptides: Vec<Vec<u32>>, scores: Vec<usize>, size: usize) -> Vec<Vec<u32>> {
+    if peptides.len() <= size { return peptides; }
+
+    let mut scored: Vec<_> = scores.into_iter().zip(peptides).collect();
+    scored.sort_by_key(|x| ::std::cmp::Reverse(x.0));
+    let cutoff = if size == 0 { usize::MAX } else { scored[size - 1].0 };
+    scored.into_iter()
+        .take_while(|&(score, _)| score >= cutoff)
+        .map(|(_, peptide)| peptide)
+        .collect()
+}
+
+/// leaderboard cyclopeptide sequencing against a noisy spectrum,
+/// returns the highest scoring peptide with the parent mass using
+/// amino acids from the given mass alphabet, which must be non-empty
+/// and have no zero masses so peptides grow every round
+///
+pub fn leaderboard_sequencing(spectrum: &[u32], alphabet: &[u32], options: &LeaderboardOptions) -> Result<Vec<u32>> {
+    if alphabet.is_empty() { return Err(Error::EmptyAlphabet); }
+    if alphabet.contains(&0) { return Err(Error::InvalidMass(0)); }
+    let (spectrum, parent) = prepare_spectrum(spectrum)?;
+    let mut leaderboard: Vec<Vec<u32>> = vec![Vec::new()];
+    let mut leader = Vec::new();
+    let mut leader_score = cyclic_score(&leader, &spectrum);
+
+    while !leaderboard.is_empty() {
+        leaderboard = expand(&leaderboard, alphabet);
+        leaderboard.retain(|peptide| peptide.iter().sum::<u32>() <= parent);
+
+        let candidates: Vec<Vec<u32>> = leaderboard.iter()
+            .filter(|peptide| peptide.iter().sum::<u32>() == parent)
+            .cloned()
+            .collect();
+        let scores = parallel_scores(&candidates, options.threads, |x| cyclic_score(x, &spectrum))?;
+        for (peptide, score) in candidates.into_iter().zip(scores) {
+            if score > leader_score {
+                leader = peptide;
+                leader_score = score;
+            }
+        }
+
+        let scores = parallel_scores(&leaderboard, options.threads, |x| linear_score(x, &spectrum))?;
+        leaderboard = trim(leaderboard, scores, options.leaderboard_size);
+    }
+    Ok(leader)
+}
+
+/// spectral convolution, every positive difference between two
+/// masses in the spectrum
+///
+pub fn spectral_convolution(spectrum: &[u32]) -> Vec<u32> {
+    let mut spectrum = spectrum.to_vec();
+    spectrum.sort();
+    let mut res = Vec::new();
+
+    for (i, &larger) in spectrum.iter().enumerate() {
+        for &smaller in &spectrum[..i] {
+            if larger > smaller { res.push(larger - smaller); }
+        }
+    }
+    res
+}
+
+/// the m most frequent convolution masses between 57 and 200,
+/// including ties, to use as a custom amino acid alphabet
+///
+pub fn convolution_alphabet(spectrum: &[u32], m: usize) -> Vec<u32> {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for mass in spectral_convolution(spectrum) {
+        if (57..=200).contains(&mass) {
+            *counts.entry(mass).or_insert(0) += 1;
+        }
+    }
+
+    let mut counts: Vec<(u32, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    if m == 0 || counts.is_empty() { return Vec::new(); }
+
+    let cutoff = counts[m.min(counts.len()) - 1].1;
+    let mut alphabet: Vec<u32> = counts.into_iter()
+        .take_while(|&(_, count)| count >= cutoff)
+        .map(|(mass, _)| mass)
+        .collect();
+    alphabet.sort();
+    alphabet
+}
+
+/// leaderboard sequencing using an alphabet chosen by spectral
+/// convolution
+///
+pub fn convolution_sequencing(spectrum: &[u32], m: usize, options: &LeaderboardOptions) -> Result<Vec<u32>> {
+    let alphabet = convolution_alphabet(spectrum, m);
+    leaderboard_sequencing(spectrum, &alphabet, options)
+}
+
+#[cfg(test)]
+mod test {
+    use super::LeaderboardOptions;
+    use proteomics::INTEGER_MASSES;
+
+    #[test]
+    fn cyclopeptide_sequencing() {
+        let spectrum = [0, 113, 128, 186, 241, 299, 314, 427];
+        let mut res = super::cyclopeptide_sequencing(&spectrum).unwrap();
+        res.sort();
+        assert_eq!(res, vec![vec![113, 128, 186], vec![113, 186, 128],
+                             vec![128, 113, 186], vec![128, 186, 113],
+                             vec![186, 113, 128], vec![186, 128, 113]]);
+        assert!(super::cyclopeptide_sequencing(&[]).is_err());
+    }
+
+    #[test]
+    fn scores() {
+        let spectrum = [0, 99, 113, 114, 128, 227, 257, 299, 355, 356, 370, 371, 484];
+        // NQEL
+        let peptide = [114, 128, 129, 113];
+        assert_eq!(super::cyclic_score(&peptide, &spectrum), 11);
+        assert_eq!(super::linear_score(&peptide, &spectrum), 8);
+    }
+
+    #[test]
+    fn leaderboard_sequencing() {
+        let spectrum = [0, 71, 113, 129, 147, 200, 218, 260, 313, 331, 347, 389, 460];
+        for threads in 1..3 {
+            let options = LeaderboardOptions { leaderboard_size: 10, threads };
+            let leader = super::leaderboard_sequencing(&spectrum, &INTEGER_MASSES, &options).unwrap();
+            assert_eq!(leader.iter().sum::<u32>(), 460);
+            assert_eq!(super::cyclic_score(&leader, &spectrum), 13);
+        }
+
+        let options = LeaderboardOptions::default();
+        assert!(super::leaderboard_sequencing(&spectrum, &[], &options).is_err());
+        assert!(super::leaderboard_sequencing(&spectrum, &[0, 71], &options).is_err());
+    }
+
+    #[test]
+    fn spectral_convolution() {
+        let mut res = super::spectral_convolution(&[0, 137, 186, 323]);
+        res.sort();
+        assert_eq!(res, vec![49, 137, 137, 186, 186, 323]);
+    }
+
+    #[test]
+    fn convolution_sequencing() {
+        let spectrum = [57, 57, 71, 99, 129, 137, 170, 186, 194, 208, 228, 265, 285,
+                        299, 307, 323, 356, 364, 394, 422, 493];
+        let options = LeaderboardOptions { leaderboard_size: 60, threads: 2 };
+        let leader = super::convolution_sequencing(&spectrum, 20, &options).unwrap();
+        let expected = [99, 71, 137, 57, 72, 57];
+        assert_eq!(super::cyclic_score(&leader, &spectrum), super::cyclic_score(&expected, &spectrum));
+    }
+}
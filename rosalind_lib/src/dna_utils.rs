@@ -1,30 +1,89 @@
+use error::{Error, Result};
+use sequence::Dna;
+
 /// reverse a String
 ///
 pub fn reverse(dna: &str) -> String {
     dna.chars().rev().collect()
 }
 
-/// complement a dna String
+/// complement a single nucleotide, including IUPAC ambiguity codes,
+/// soft-masked (lowercase) bases stay lowercase
 ///
-pub fn complement(dna: &str) -> String {
-    // map returns an iterator, collect
-    // collects iterator characters into string
-    dna.chars().map(|c| {
-        match c {
-            'A' => 'T',
-            'C' => 'G',
-            'G' => 'C',
-            'T' => 'A',
-             _  => 'N',
-        }
+pub(crate) fn complement_base(c: char) -> Option<char> {
+    let complement = match c.to_ascii_uppercase() {
+        'A' => Some('T'),
+        'C' => Some('G'),
+        'G' => Some('C'),
+        'T' => Some('A'),
+        'R' => Some('Y'),
+        'Y' => Some('R'),
+        'S' => Some('S'),
+        'W' => Some('W'),
+        'K' => Some('M'),
+        'M' => Some('K'),
+        'B' => Some('V'),
+        'V' => Some('B'),
+        'D' => Some('H'),
+        'H' => Some('D'),
+        'N' => Some('N'),
+         _  => None,
+    };
+    if c.is_ascii_lowercase() {
+        complement.map(|x| x.to_ascii_lowercase())
+    } else {
+        complement
+    }
+}
+
+/// set of bases an IUPAC nucleotide code stands for, as
+/// a bitmask with A = 1, C = 2, G = 4, T = 8, ignores case
+///
+pub fn iupac_bases(c: u8) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        b'A' => Some(0b0001),
+        b'C' => Some(0b0010),
+        b'G' => Some(0b0100),
+        b'T' => Some(0b1000),
+        b'R' => Some(0b0101),
+        b'Y' => Some(0b1010),
+        b'S' => Some(0b0110),
+        b'W' => Some(0b1001),
+        b'K' => Some(0b1100),
+        b'M' => Some(0b0011),
+        b'B' => Some(0b1110),
+        b'D' => Some(0b1101),
+        b'H' => Some(0b1011),
+        b'V' => Some(0b0111),
+        b'N' => Some(0b1111),
+         _   => None,
+    }
+}
+
+/// complement a dna String, IUPAC ambiguity codes are
+/// mapped to the code for the complementary bases and
+/// case is preserved
+///
+pub fn complement(dna: &str) -> Result<String> {
+    // map returns an iterator, collect stops
+    // at the first invalid character
+    dna.char_indices().map(|(i, c)| {
+        complement_base(c).ok_or(Error::InvalidCharacter(c, i))
     }).collect()
 }
 
-/// Compute reverse complement
+/// Compute reverse complement, keeps soft-masking
 ///
-pub fn revcomp(dna: &str) -> String {
-    let revdna = reverse(&dna);
-    complement(&revdna)
+pub fn revcomp(dna: &str) -> Result<String> {
+    // complement first so error positions refer to the input
+    let compdna = complement(dna)?;
+    Ok(reverse(&compdna))
+}
+
+/// reverse complement of a validated DNA sequence
+///
+pub fn revcomp_dna(dna: &Dna) -> Dna {
+    dna.revcomp()
 }
 
 /// edit distance between strings
@@ -35,41 +94,69 @@ pub fn num_mismatches(left: &[u8], right: &[u8]) -> usize {
         .count()
 }
 
-use std::collections::BTreeMap;
+/// check that two strings differ in at most d positions, stopping
+/// at the first mismatch past d
+///
+pub fn within_distance(left: &[u8], right: &[u8], d: usize) -> bool {
+    let mut mismatches = 0;
+    for (x, y) in left.iter().zip(right) {
+        if x != y {
+            mismatches += 1;
+            if mismatches > d { return false; }
+        }
+    }
+    true
+}
 
-/// count nucleotides in a string
+/// count nucleotides in a string, ignoring case
 ///
-pub fn count_nucleotides(string: &str) -> Vec<i32> {
-    // use a BTreeMap to order the keys of the hash so that
-    // keys are ordered (ACGT)
-    let mut map = BTreeMap::new();
+pub fn count_nucleotides(string: &str) -> Result<Vec<i32>> {
+    // counts are always returned in ACGT order
+    let mut counts = vec![0; 4];
 
-    // add 1 to counter hash entry for each character
-    // in string
-    for c in string.chars() {
-        *map.entry(c).or_insert(0) += 1;
+    for (i, c) in string.char_indices() {
+        let index = match c.to_ascii_uppercase() {
+            'A' => 0,
+            'C' => 1,
+            'G' => 2,
+            'T' => 3,
+             _  => return Err(Error::InvalidCharacter(c, i)),
+        };
+        counts[index] += 1;
     }
+    Ok(counts)
+}
 
-    // return vector of nucleotide counts
-    map.values().cloned().collect()
+/// count nucleotides in a validated DNA sequence, in ACGT order,
+/// never fails since every base is already known to be valid
+///
+pub fn count_nucleotides_dna(dna: &Dna) -> Vec<i32> {
+    dna.counts().into_iter().map(|x| x as i32).collect()
 }
 
-pub fn gc_content(dna: &str) -> f32 {
+/// percentage of G and C bases, ignoring case
+///
+pub fn gc_content(dna: &str) -> Result<f32> {
+    if dna.is_empty() { return Err(Error::EmptySequence); }
+
     let mut count = 0.0;
-    for c in dna.chars() {
-        count += match c {
+    for (i, c) in dna.char_indices() {
+        count += match c.to_ascii_uppercase() {
             'C' | 'G' => 1.0,
             'A' | 'T' => 0.0,
-            _ => unreachable!(),
+            _ => return Err(Error::InvalidCharacter(c, i)),
         }
     }
-    100.0 * count / dna.len() as f32
+    Ok(100.0 * count / dna.len() as f32)
 }
 
-/// Transcribe DNA to RNA
+/// Transcribe DNA to RNA, keeps soft-masking
 ///
-pub fn transcribe(dna: &str) -> String {
-    dna.replace("T", "U")
+pub fn transcribe(dna: &str) -> Result<String> {
+    if let Some((i, c)) = dna.char_indices().find(|&(_, c)| !"ACGTacgt".contains(c)) {
+        return Err(Error::InvalidCharacter(c, i));
+    }
+    Ok(dna.replace('T', "U").replace('t', "u"))
 }
 
 #[cfg(test)]
@@ -84,15 +171,43 @@ mod test {
     #[test]
     fn complement() {
         let dna = "AGCTTA";
-        let res = super::complement(dna);
+        let res = super::complement(dna).unwrap();
         assert_eq!(res, "TCGAAT");
     }
 
+    #[test]
+    fn complement_iupac() {
+        let res = super::complement("RYSWKMBDHVN").unwrap();
+        assert_eq!(res, "YRSWMKVHDBN");
+        assert_eq!(super::revcomp("GAATTCNNNR").unwrap(), "YNNNGAATTC");
+    }
+
+    #[test]
+    fn iupac_bases() {
+        for (code, complement) in "ACGTRYSWKMBDHVN".chars().zip("TGCAYRSWMKVHDBN".chars()) {
+            // complementing a code complements each base it stands for
+            let bases = super::iupac_bases(code as u8).unwrap();
+            let expected = (0..4).filter(|i| bases & (1 << i) != 0)
+                .fold(0, |acc, i| acc | (1 << (3 - i)));
+            assert_eq!(super::iupac_bases(complement as u8), Some(expected));
+        }
+        assert_eq!(super::iupac_bases(b'X'), None);
+    }
+
     #[test]
     fn revcomp() {
         let dna = "AAAACCCGGT";
-        let res = super::revcomp(dna);
+        let res = super::revcomp(dna).unwrap();
         assert_eq!(res, "ACCGGGTTTT");
+        assert!(matches!(super::revcomp("AXCGT"), Err(super::Error::InvalidCharacter('X', 1))));
+    }
+
+    #[test]
+    fn dna_entry_points() {
+        use sequence::Dna;
+        let dna = Dna::new("AAAACCCGGT").unwrap();
+        assert_eq!(super::revcomp_dna(&dna).as_str(), "ACCGGGTTTT");
+        assert_eq!(super::count_nucleotides_dna(&dna), vec![4, 3, 2, 1]);
     }
 
     #[test]
@@ -102,23 +217,55 @@ mod test {
         assert_eq!(super::num_mismatches(b"TAT",b"AAA"),2);
     }
 
+    #[test]
+    fn within_distance() {
+        assert!(super::within_distance(b"TAT", b"AAA", 2));
+        assert!(!super::within_distance(b"TAT", b"AAA", 1));
+        assert!(super::within_distance(b"", b"", 0));
+        assert_eq!(super::within_distance(b"ACGTT", b"TGCAT", 3),
+                   super::num_mismatches(b"ACGTT", b"TGCAT") <= 3);
+    }
+
     #[test]
     fn count_nucleotides() {
         let string = "AGCTTTTCATTCTGACTGCAACGGGCAATATGTCTCTGTGTGGATTAAAAAAAGAGTGTCTGATAGCAGC";
-        let res = super::count_nucleotides(string);
+        let res = super::count_nucleotides(string).unwrap();
         assert_eq!(res, vec![20, 12, 17, 21])
     }
 
     #[test]
     fn gc_content() {
         let dna = "AGCTATAG";
-        assert_eq!(super::gc_content(dna), 37.5);
+        assert_eq!(super::gc_content(dna).unwrap(), 37.5);
     }
 
     #[test]
     fn transcribe() {
         let dna = "GATGGAACTTGACTACGTAAATT";
-        let res = super::transcribe(dna);
+        let res = super::transcribe(dna).unwrap();
         assert_eq!(res, "GAUGGAACUUGACUACGUAAAUU")
     }
+
+    #[test]
+    fn invalid_characters() {
+        use error::Error;
+        match super::complement("ACXT") {
+            Err(Error::InvalidCharacter('X', 2)) => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert!(super::revcomp("ACGU").is_err());
+        assert!(super::count_nucleotides("ACGTX").is_err());
+        assert!(super::gc_content("ACGN").is_err());
+        assert!(super::gc_content("").is_err());
+        assert!(super::transcribe("ACGU").is_err());
+    }
+
+    #[test]
+    fn soft_masked() {
+        assert_eq!(super::complement("ACgtNn").unwrap(), "TGcaNn");
+        assert_eq!(super::revcomp("AAAAcccGGT").unwrap(), "ACCgggTTTT");
+        assert_eq!(super::count_nucleotides("ACgtaa").unwrap(), vec![3, 1, 1, 1]);
+        assert_eq!(super::gc_content("AGctATag").unwrap(), 37.5);
+        assert_eq!(super::transcribe("GATtaca").unwrap(), "GAUuaca");
+    }
 }
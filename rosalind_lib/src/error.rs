@@ -0,0 +1,92 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::result;
+
+use io::{FastaError, FastqError};
+
+/// errors returned by the library instead of panicking
+///
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Fasta(FastaError),
+    Fastq(FastqError),
+    InvalidCharacter(char, usize),
+    EmptySequence,
+    EmptyKmer,
+    KmerTooLong(usize, usize),
+    KmerTooLarge(usize, usize),
+    InvalidKmerIndex(u128, usize),
+    UnknownGeneticCode(u8),
+    EmptyAlphabet,
+    InvalidMass(u32),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::Fasta(ref err) => write!(f, "FASTA error: {}", err),
+            Error::Fastq(ref err) => write!(f, "FASTQ error: {}", err),
+            Error::InvalidCharacter(c, position) =>
+                write!(f, "invalid character '{}' at position {}", c, position),
+            Error::EmptySequence => write!(f, "sequence is empty"),
+            Error::EmptyKmer => write!(f, "k must be greater than 0"),
+            Error::KmerTooLong(k, n) =>
+                write!(f, "k ({}) is larger than the sequence length ({})", k, n),
+            Error::KmerTooLarge(k, max) =>
+                write!(f, "k ({}) is larger than the longest packed kmer ({})", k, max),
+            Error::InvalidKmerIndex(index, k) =>
+                write!(f, "index {} is out of range for kmers of length {}", index, k),
+            Error::UnknownGeneticCode(id) => write!(f, "unknown genetic code table {}", id),
+            Error::EmptyAlphabet => write!(f, "amino acid mass alphabet is empty"),
+            Error::InvalidMass(mass) => write!(f, "invalid amino acid mass {}", mass),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            Error::Fasta(ref err) => Some(err),
+            Error::Fastq(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<FastaError> for Error {
+    fn from(err: FastaError) -> Error {
+        match err {
+            FastaError::Io(err) => Error::Io(err),
+            err => Error::Fasta(err),
+        }
+    }
+}
+
+impl From<FastqError> for Error {
+    fn from(err: FastqError) -> Error {
+        match err {
+            FastqError::Io(err) => Error::Io(err),
+            err => Error::Fastq(err),
+        }
+    }
+}
+
+/// check that a k-mer length fits in a sequence of length n
+///
+pub(crate) fn check_k(k: usize, n: usize) -> Result<()> {
+    if k == 0 { return Err(Error::EmptyKmer); }
+    if k > n { return Err(Error::KmerTooLong(k, n)); }
+    Ok(())
+}
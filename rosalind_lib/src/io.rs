@@ -1,39 +1,1031 @@
+extern crate flate2;
+use self::flate2::bufread::MultiGzDecoder;
+
+use std::error;
+use std::fmt;
 use std::fs::File;
+use std::io;
 use std::io::BufReader;
 use std::io::BufRead;
+use std::io::Write;
+use std::io::{Read, Seek, SeekFrom};
 use std::collections::HashMap;
 
-pub fn parse_fasta_file(filename: &str) -> HashMap<String, String> {
-    let fhandle = File::open(filename)
-        .ok()
-        .expect("Could not open file");
-    let mut lines = BufReader::new(fhandle).lines();
-    let mut records = HashMap::new();
+use error::Error;
+
+/// first two bytes of any gzip stream
+///
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// wrap a reader so gzip-compressed input is decompressed on the fly,
+/// uncompressed input is passed through untouched
+///
+pub fn decompress<R: BufRead + 'static>(mut reader: R) -> io::Result<Box<dyn BufRead>> {
+    let is_gzip = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+    if is_gzip {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// check whether a file starts with the gzip magic bytes, leaves
+/// the file positioned at its start
+///
+fn is_gzip_file(fhandle: &mut File) -> io::Result<bool> {
+    let mut magic = [0u8; 2];
+    let nbytes = fhandle.read(&mut magic)?;
+    fhandle.seek(SeekFrom::Start(0))?;
+    Ok(nbytes == magic.len() && magic == GZIP_MAGIC)
+}
+
+/// open a file for reading, plain text or gzip-compressed
+///
+pub fn open_file(filename: &str) -> io::Result<Box<dyn BufRead>> {
+    let fhandle = File::open(filename)?;
+    decompress(BufReader::new(fhandle))
+}
+
+/// a single FASTA record
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastaRecord {
+    pub id: String,
+    pub description: Option<String>,
+    pub sequence: String,
+}
+
+/// errors found while reading FASTA input
+///
+#[derive(Debug)]
+pub enum FastaError {
+    Io(io::Error),
+    SequenceBeforeHeader(usize),
+    EmptyRecord(String),
+    UnknownSequence(String),
+    InvalidRegion(String, usize, usize),
+    InconsistentLineLength(String),
+    InvalidIndex(usize),
+    CompressedInput(String),
+}
+
+impl fmt::Display for FastaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FastaError::Io(ref err) => write!(f, "I/O error: {}", err),
+            FastaError::SequenceBeforeHeader(line) =>
+                write!(f, "sequence found before first header on line {}", line),
+            FastaError::EmptyRecord(ref id) => write!(f, "record '{}' has no sequence", id),
+            FastaError::UnknownSequence(ref id) => write!(f, "sequence '{}' not found in index", id),
+            FastaError::InvalidRegion(ref id, start, end) =>
+                write!(f, "region {}-{} is out of bounds for sequence '{}'", start, end, id),
+            FastaError::InconsistentLineLength(ref id) =>
+                write!(f, "record '{}' has lines of different lengths and can't be indexed", id),
+            FastaError::InvalidIndex(line) => write!(f, "malformed index entry on line {}", line),
+            FastaError::CompressedInput(ref filename) =>
+                write!(f, "file '{}' is gzip-compressed and can't be indexed", filename),
+        }
+    }
+}
+
+impl error::Error for FastaError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            FastaError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FastaError {
+    fn from(err: io::Error) -> FastaError {
+        FastaError::Io(err)
+    }
+}
+
+/// streaming FASTA reader, yields records in file order
+///
+pub struct FastaReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    header: Option<String>,
+    done: bool,
+}
+
+impl FastaReader<Box<dyn BufRead>> {
+    /// open a FASTA file for reading, may be gzip-compressed
+    ///
+    pub fn from_file(filename: &str) -> Result<Self, FastaError> {
+        Ok(FastaReader::new(open_file(filename)?))
+    }
+}
+
+impl<R: BufRead> FastaReader<R> {
+    pub fn new(reader: R) -> Self {
+        FastaReader {
+            lines: reader.lines(),
+            line_number: 0,
+            header: None,
+            done: false,
+        }
+    }
 
-    let mut id = String::new();
-    let mut dna = String::new();
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+        self.line_number += 1;
+        self.lines.next().map(|line| {
+            line.map(|mut line| {
+                if line.ends_with('\r') { line.pop(); }
+                line
+            })
+        })
+    }
+
+    fn build_record(header: &str, sequence: String) -> Result<FastaRecord, FastaError> {
+        let mut fields = header.splitn(2, char::is_whitespace);
+        let id = fields.next().unwrap_or("").to_string();
+        let description = fields.next()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty());
+
+        if sequence.is_empty() {
+            return Err(FastaError::EmptyRecord(id));
+        }
+        Ok(FastaRecord { id, description, sequence })
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = Result<FastaRecord, FastaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+
+        // find the header for this record, either left over from the
+        // previous call or the first header in the input
+        let header = match self.header.take() {
+            Some(header) => header,
+            None => loop {
+                match self.next_line() {
+                    None => { self.done = true; return None; },
+                    Some(Err(err)) => { self.done = true; return Some(Err(err.into())); },
+                    Some(Ok(line)) => {
+                        if let Some(header) = line.strip_prefix('>') {
+                            break header.to_string();
+                        }
+                        if !line.trim().is_empty() {
+                            self.done = true;
+                            return Some(Err(FastaError::SequenceBeforeHeader(self.line_number)));
+                        }
+                    },
+                }
+            },
+        };
+
+        // accumulate sequence lines until the next header
+        let mut sequence = String::new();
+        loop {
+            match self.next_line() {
+                None => { self.done = true; break; },
+                Some(Err(err)) => { self.done = true; return Some(Err(err.into())); },
+                Some(Ok(line)) => {
+                    if let Some(next_header) = line.strip_prefix('>') {
+                        self.header = Some(next_header.to_string());
+                        break;
+                    }
+                    sequence.push_str(line.trim());
+                },
+            }
+        }
+        Some(Self::build_record(&header, sequence))
+    }
+}
+
+/// quality score encodings used by FASTQ files
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhredEncoding {
+    Phred33,
+    Phred64,
+}
+
+impl PhredEncoding {
+    fn offset(&self) -> u8 {
+        match *self {
+            PhredEncoding::Phred33 => 33,
+            PhredEncoding::Phred64 => 64,
+        }
+    }
+
+    /// decode a quality string into Phred scores
+    ///
+    pub fn decode(&self, quality: &str) -> Option<Vec<u8>> {
+        let offset = self.offset();
+        quality.bytes()
+            .map(|c| if (offset..=b'~').contains(&c) { Some(c - offset) } else { None })
+            .collect()
+    }
+
+    /// encode Phred scores as a quality string, returns None if a
+    /// score is too high to be written in this encoding
+    ///
+    pub fn encode(&self, quality: &[u8]) -> Option<String> {
+        let offset = self.offset();
+        quality.iter()
+            .map(|&q| if q <= b'~' - offset { Some((q + offset) as char) } else { None })
+            .collect()
+    }
+}
+
+/// a single FASTQ record with decoded quality scores
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastqRecord {
+    pub id: String,
+    pub description: Option<String>,
+    pub sequence: String,
+    pub quality: Vec<u8>,
+}
+
+impl FastqRecord {
+    /// mean Phred quality over the read
+    ///
+    pub fn mean_quality(&self) -> f64 {
+        if self.quality.is_empty() { return 0.0; }
+        let total: u64 = self.quality.iter().map(|&q| q as u64).sum();
+        total as f64 / self.quality.len() as f64
+    }
+
+    /// remove trailing bases with quality below threshold
+    ///
+    pub fn trim_low_quality_tail(&mut self, threshold: u8) {
+        let keep = self.quality.iter()
+            .rposition(|&q| q >= threshold)
+            .map_or(0, |i| i + 1);
+        self.sequence.truncate(keep);
+        self.quality.truncate(keep);
+    }
+}
+
+/// errors found while reading FASTQ input
+///
+#[derive(Debug)]
+pub enum FastqError {
+    Io(io::Error),
+    MissingHeader(usize),
+    MissingSeparator(String),
+    InvalidQuality(String),
+    LengthMismatch(String),
+}
 
-    while let Some(Ok(curline)) = lines.next() {
-        if curline.starts_with(">") {
-            if id.len() > 0 { records.insert(id, dna); };
-            id = curline[1..].to_string();
-            dna = String::new();
-        } else {
-            dna.push_str(&curline);
+impl fmt::Display for FastqError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FastqError::Io(ref err) => write!(f, "I/O error: {}", err),
+            FastqError::MissingHeader(line) =>
+                write!(f, "expected '@' header on line {}", line),
+            FastqError::MissingSeparator(ref id) =>
+                write!(f, "record '{}' has no '+' separator line", id),
+            FastqError::InvalidQuality(ref id) =>
+                write!(f, "record '{}' has quality characters outside the encoding range", id),
+            FastqError::LengthMismatch(ref id) =>
+                write!(f, "record '{}' has sequence and quality of different lengths", id),
         }
     }
-    if id.len() > 0 { records.insert(id, dna); };
-    records
+}
+
+impl error::Error for FastqError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            FastqError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FastqError {
+    fn from(err: io::Error) -> FastqError {
+        FastqError::Io(err)
+    }
+}
+
+/// streaming FASTQ reader, supports sequence and quality split
+/// over multiple lines
+///
+pub struct FastqReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    encoding: PhredEncoding,
+    done: bool,
+}
+
+impl FastqReader<Box<dyn BufRead>> {
+    /// open a FASTQ file for reading, may be gzip-compressed
+    ///
+    pub fn from_file(filename: &str, encoding: PhredEncoding) -> Result<Self, FastqError> {
+        Ok(FastqReader::new(open_file(filename)?, encoding))
+    }
+}
+
+impl<R: BufRead> FastqReader<R> {
+    pub fn new(reader: R, encoding: PhredEncoding) -> Self {
+        FastqReader {
+            lines: reader.lines(),
+            line_number: 0,
+            encoding,
+            done: false,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+        self.line_number += 1;
+        self.lines.next().map(|line| {
+            line.map(|mut line| {
+                if line.ends_with('\r') { line.pop(); }
+                line
+            })
+        })
+    }
+
+    fn read_record(&mut self) -> Option<Result<FastqRecord, FastqError>> {
+        // skip blank lines between records
+        let header = loop {
+            match self.next_line() {
+                None => return None,
+                Some(Err(err)) => return Some(Err(err.into())),
+                Some(Ok(line)) => if !line.trim().is_empty() { break line; },
+            }
+        };
+        let header = match header.strip_prefix('@') {
+            Some(header) => header.to_string(),
+            None => return Some(Err(FastqError::MissingHeader(self.line_number))),
+        };
+        let mut fields = header.splitn(2, char::is_whitespace);
+        let id = fields.next().unwrap_or("").to_string();
+        let description = fields.next()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty());
+
+        // sequence lines run until the '+' separator
+        let mut sequence = String::new();
+        loop {
+            match self.next_line() {
+                None => return Some(Err(FastqError::MissingSeparator(id))),
+                Some(Err(err)) => return Some(Err(err.into())),
+                Some(Ok(line)) => {
+                    if line.starts_with('+') { break; }
+                    sequence.push_str(line.trim());
+                },
+            }
+        }
+
+        // quality lines may start with '@' or '+', so read them
+        // until they cover the whole sequence
+        let mut quality = String::new();
+        while quality.len() < sequence.len() {
+            match self.next_line() {
+                None => break,
+                Some(Err(err)) => return Some(Err(err.into())),
+                Some(Ok(line)) => quality.push_str(line.trim()),
+            }
+        }
+        if quality.len() != sequence.len() {
+            return Some(Err(FastqError::LengthMismatch(id)));
+        }
+
+        let quality = match self.encoding.decode(&quality) {
+            Some(quality) => quality,
+            None => return Some(Err(FastqError::InvalidQuality(id))),
+        };
+        Some(Ok(FastqRecord { id, description, sequence, quality }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastqReader<R> {
+    type Item = Result<FastqRecord, FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+
+        let res = self.read_record();
+        match res {
+            Some(Ok(_)) => (),
+            _ => self.done = true,
+        }
+        res
+    }
+}
+
+/// one line of a samtools-compatible .fai index
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaiEntry {
+    pub name: String,
+    pub length: u64,
+    pub offset: u64,
+    pub line_bases: u64,
+    pub line_width: u64,
+}
+
+impl FaiEntry {
+    /// byte offset in the FASTA file of a sequence position
+    ///
+    fn position_offset(&self, position: u64) -> u64 {
+        if self.line_bases == 0 { return self.offset; }
+        self.offset + (position / self.line_bases) * self.line_width + position % self.line_bases
+    }
+}
+
+/// index of a FASTA file, one entry per record in file order
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastaIndex {
+    entries: Vec<FaiEntry>,
+    // position in `entries` of each name, the first record wins
+    // when a name is repeated
+    positions: HashMap<String, usize>,
+}
+
+impl FastaIndex {
+    fn from_entries(entries: Vec<FaiEntry>) -> Self {
+        let mut positions = HashMap::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            positions.entry(entry.name.clone()).or_insert(i);
+        }
+        FastaIndex { entries, positions }
+    }
+
+    /// index a FASTA file, offsets refer to bytes on disk so the
+    /// file must not be compressed
+    ///
+    pub fn from_fasta_file(filename: &str) -> Result<Self, FastaError> {
+        let mut fhandle = File::open(filename)?;
+        if is_gzip_file(&mut fhandle)? {
+            return Err(FastaError::CompressedInput(filename.to_string()));
+        }
+        FastaIndex::build(BufReader::new(fhandle))
+    }
+
+    /// index FASTA input by scanning it once
+    ///
+    pub fn build<R: BufRead>(mut reader: R) -> Result<Self, FastaError> {
+        let mut entries: Vec<FaiEntry> = Vec::new();
+        let mut offset = 0u64;
+        let mut line_number = 0;
+        let mut line = Vec::new();
+
+        // set once a record has a line shorter than the first one,
+        // any further sequence line for that record can't be indexed
+        let mut last_line_seen = false;
+
+        loop {
+            line.clear();
+            let nbytes = reader.read_until(b'\n', &mut line)? as u64;
+            if nbytes == 0 { break; }
+            line_number += 1;
+            offset += nbytes;
+
+            let mut bases = line.len();
+            while bases > 0 && (line[bases - 1] == b'\n' || line[bases - 1] == b'\r') {
+                bases -= 1;
+            }
+            let bases = bases as u64;
+
+            if line.starts_with(b">") {
+                let header = String::from_utf8_lossy(&line[1..bases as usize]).into_owned();
+                let name = header.split_whitespace().next().unwrap_or("").to_string();
+                entries.push(FaiEntry {
+                    name,
+                    length: 0,
+                    offset,
+                    line_bases: 0,
+                    line_width: 0,
+                });
+                last_line_seen = false;
+                continue;
+            }
+
+            let entry = match entries.last_mut() {
+                Some(entry) => entry,
+                None if bases == 0 => continue,
+                None => return Err(FastaError::SequenceBeforeHeader(line_number)),
+            };
+            if bases == 0 {
+                last_line_seen = true;
+                continue;
+            }
+            if last_line_seen {
+                return Err(FastaError::InconsistentLineLength(entry.name.clone()));
+            }
+            // only the final line of the file can lack a terminator
+            let terminated = line.last() == Some(&b'\n');
+            if entry.line_bases == 0 {
+                entry.line_bases = bases;
+                entry.line_width = nbytes;
+            } else if bases > entry.line_bases
+                || (terminated && nbytes - bases != entry.line_width - entry.line_bases) {
+                return Err(FastaError::InconsistentLineLength(entry.name.clone()));
+            }
+            if bases < entry.line_bases { last_line_seen = true; }
+            entry.length += bases;
+        }
+
+        if let Some(entry) = entries.iter().find(|x| x.length == 0) {
+            return Err(FastaError::EmptyRecord(entry.name.clone()));
+        }
+        Ok(FastaIndex::from_entries(entries))
+    }
+
+    /// read an existing .fai file
+    ///
+    pub fn from_file(filename: &str) -> Result<Self, FastaError> {
+        let fhandle = File::open(filename)?;
+        FastaIndex::read(BufReader::new(fhandle))
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, FastaError> {
+        let mut entries = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() { continue; }
+
+            let fields: Vec<&str> = line.trim_end().split('\t').collect();
+            if fields.len() < 5 { return Err(FastaError::InvalidIndex(i + 1)); }
+            let mut values = [0u64; 4];
+            for (value, field) in values.iter_mut().zip(&fields[1..5]) {
+                *value = field.parse().map_err(|_| FastaError::InvalidIndex(i + 1))?;
+            }
+            entries.push(FaiEntry {
+                name: fields[0].to_string(),
+                length: values[0],
+                offset: values[1],
+                line_bases: values[2],
+                line_width: values[3],
+            });
+        }
+        Ok(FastaIndex::from_entries(entries))
+    }
+
+    /// write the index in .fai format
+    ///
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for entry in &self.entries {
+            writeln!(writer, "{}\t{}\t{}\t{}\t{}",
+                     entry.name, entry.length, entry.offset, entry.line_bases, entry.line_width)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FaiEntry> {
+        self.positions.get(name).map(|&i| &self.entries[i])
+    }
+
+    pub fn entries(&self) -> &[FaiEntry] {
+        &self.entries
+    }
+}
+
+/// random access to the records of an indexed FASTA file
+///
+pub struct IndexedFastaReader<R: Read + Seek> {
+    reader: R,
+    index: FastaIndex,
+}
+
+impl IndexedFastaReader<File> {
+    /// open a FASTA file using its .fai index, the index is
+    /// built in memory if `filename.fai` does not exist
+    ///
+    pub fn from_file(filename: &str) -> Result<Self, FastaError> {
+        let mut fhandle = File::open(filename)?;
+        if is_gzip_file(&mut fhandle)? {
+            return Err(FastaError::CompressedInput(filename.to_string()));
+        }
+
+        let fai_filename = format!("{}.fai", filename);
+        let index = match File::open(&fai_filename) {
+            Ok(fhandle) => FastaIndex::read(BufReader::new(fhandle))?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound =>
+                FastaIndex::from_fasta_file(filename)?,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(IndexedFastaReader::new(fhandle, index))
+    }
+}
+
+impl<R: Read + Seek> IndexedFastaReader<R> {
+    pub fn new(reader: R, index: FastaIndex) -> Self {
+        IndexedFastaReader { reader, index }
+    }
+
+    pub fn index(&self) -> &FastaIndex {
+        &self.index
+    }
+
+    /// fetch the subsequence of `id` in the 0-based, half-open
+    /// interval [start, end)
+    ///
+    pub fn fetch(&mut self, id: &str, start: usize, end: usize) -> Result<String, FastaError> {
+        let entry = match self.index.get(id) {
+            Some(entry) => entry,
+            None => return Err(FastaError::UnknownSequence(id.to_string())),
+        };
+        if start > end || end as u64 > entry.length {
+            return Err(FastaError::InvalidRegion(id.to_string(), start, end));
+        }
+        if start == end { return Ok(String::new()); }
+
+        let first = entry.position_offset(start as u64);
+        let last = entry.position_offset(end as u64 - 1);
+        let mut buffer = vec![0u8; (last - first + 1) as usize];
+        self.reader.seek(SeekFrom::Start(first))?;
+        self.reader.read_exact(&mut buffer)?;
+
+        buffer.retain(|&c| c != b'\n' && c != b'\r');
+        String::from_utf8(buffer)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+    }
+
+    /// fetch the full sequence of `id`
+    ///
+    pub fn fetch_all(&mut self, id: &str) -> Result<String, FastaError> {
+        let length = match self.index.get(id) {
+            Some(entry) => entry.length as usize,
+            None => return Err(FastaError::UnknownSequence(id.to_string())),
+        };
+        self.fetch(id, 0, length)
+    }
+}
+
+/// default number of sequence characters per FASTA line
+///
+pub const DEFAULT_LINE_WIDTH: usize = 60;
+
+/// FASTA writer, wraps sequence lines at a fixed width
+///
+pub struct FastaWriter<W: Write> {
+    writer: W,
+    line_width: usize,
+}
+
+impl FastaWriter<File> {
+    /// create a FASTA file for writing
+    ///
+    pub fn to_file(filename: &str) -> io::Result<Self> {
+        let fhandle = File::create(filename)?;
+        Ok(FastaWriter::new(fhandle))
+    }
+}
+
+impl<W: Write> FastaWriter<W> {
+    pub fn new(writer: W) -> Self {
+        FastaWriter::with_line_width(writer, DEFAULT_LINE_WIDTH)
+    }
+
+    /// a line width of 0 writes each sequence on a single line
+    ///
+    pub fn with_line_width(writer: W, line_width: usize) -> Self {
+        FastaWriter { writer, line_width }
+    }
+
+    pub fn write_record(&mut self, record: &FastaRecord) -> io::Result<()> {
+        self.write(&record.id, record.description.as_deref(), &record.sequence)
+    }
+
+    pub fn write(&mut self, id: &str, description: Option<&str>, sequence: &str) -> io::Result<()> {
+        if sequence.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("record '{}' has no sequence", id)));
+        }
+        match description {
+            Some(description) => writeln!(self.writer, ">{} {}", id, description)?,
+            None => writeln!(self.writer, ">{}", id)?,
+        }
+        write_wrapped(&mut self.writer, sequence, self.line_width)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn write_wrapped<W: Write>(writer: &mut W, sequence: &str, line_width: usize) -> io::Result<()> {
+    if line_width == 0 {
+        return writeln!(writer, "{}", sequence);
+    }
+    for line in sequence.as_bytes().chunks(line_width) {
+        writer.write_all(line)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// FASTQ writer, writes each record as four lines
+///
+pub struct FastqWriter<W: Write> {
+    writer: W,
+    encoding: PhredEncoding,
+}
+
+impl FastqWriter<File> {
+    /// create a FASTQ file for writing
+    ///
+    pub fn to_file(filename: &str, encoding: PhredEncoding) -> io::Result<Self> {
+        let fhandle = File::create(filename)?;
+        Ok(FastqWriter::new(fhandle, encoding))
+    }
+}
+
+impl<W: Write> FastqWriter<W> {
+    pub fn new(writer: W, encoding: PhredEncoding) -> Self {
+        FastqWriter { writer, encoding }
+    }
+
+    pub fn write_record(&mut self, record: &FastqRecord) -> io::Result<()> {
+        if record.sequence.len() != record.quality.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("record '{}' has sequence and quality of different lengths", record.id)));
+        }
+        let quality = match self.encoding.encode(&record.quality) {
+            Some(quality) => quality,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              format!("record '{}' has quality scores that can't be encoded", record.id))),
+        };
+        match record.description {
+            Some(ref description) => writeln!(self.writer, "@{} {}", record.id, description)?,
+            None => writeln!(self.writer, "@{}", record.id)?,
+        }
+        writeln!(self.writer, "{}", record.sequence)?;
+        writeln!(self.writer, "+")?;
+        writeln!(self.writer, "{}", quality)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub fn parse_fasta_file(filename: &str) -> Result<HashMap<String, String>, Error> {
+    let reader = FastaReader::from_file(filename)?;
+    let mut records = HashMap::new();
+
+    for record in reader {
+        let record = record?;
+        records.insert(record.id, record.sequence);
+    }
+    Ok(records)
 }
 
 #[cfg(test)]
 mod test {
+    use std::io::Cursor;
+    use super::{FastaError, FastaReader};
+
     #[test]
     fn parse_fasta_file() {
-        let records = super::parse_fasta_file("test.fa");
+        let records = super::parse_fasta_file("test.fa").unwrap();
         assert!(records.contains_key("Rosalind_6404"));
         assert!(records.contains_key("Rosalind_5959"));
         assert!(records.contains_key("Rosalind_0808"));
         assert_eq!(records.get("Rosalind_6404").unwrap(), "CCTGCGGAAGATCGGCACTAGAATAGCCAGAACCGTTTCTCTGAGGCTTCCGGCCTTCCCTCCCACTAATAATTCTGAGG");
+        assert!(super::parse_fasta_file("missing.fa").is_err());
+    }
+
+    #[test]
+    fn fasta_reader() {
+        let reader = FastaReader::from_file("test.fa").unwrap();
+        let records: Vec<_> = reader.map(|x| x.unwrap()).collect();
+        let ids: Vec<_> = records.iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, vec!["Rosalind_6404", "Rosalind_5959", "Rosalind_0808"]);
+        assert_eq!(records[1].sequence, "CCATCGGTAGCGCATCCTTAGTCCAATTAAGTCCCTATCCAGGCGCTCCGCCGAAGGTCTATATCCATTTGTCAGCAGACACGC");
+
+        let input = ">seq1 first read\r\nACGT\r\nAC\r\n\n>seq2\nGG\n>seq1\nTT\n";
+        let records: Vec<_> = FastaReader::new(Cursor::new(input))
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].id, "seq1");
+        assert_eq!(records[0].description, Some("first read".to_string()));
+        assert_eq!(records[0].sequence, "ACGTAC");
+        assert_eq!(records[1].description, None);
+        assert_eq!(records[2].id, "seq1");
+        assert_eq!(records[2].sequence, "TT");
+    }
+
+    #[test]
+    fn fasta_reader_errors() {
+        let mut reader = FastaReader::new(Cursor::new("\nACGT\n>seq1\nACGT\n"));
+        match reader.next() {
+            Some(Err(FastaError::SequenceBeforeHeader(2))) => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert!(reader.next().is_none());
+
+        let mut reader = FastaReader::new(Cursor::new(">seq1\n>seq2\nACGT\n"));
+        match reader.next() {
+            Some(Err(FastaError::EmptyRecord(ref id))) if id == "seq1" => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert_eq!(reader.next().unwrap().unwrap().id, "seq2");
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn phred_encoding() {
+        use super::PhredEncoding;
+        assert_eq!(PhredEncoding::Phred33.decode("!+5I"), Some(vec![0, 10, 20, 40]));
+        assert_eq!(PhredEncoding::Phred64.decode("@JTh"), Some(vec![0, 10, 20, 40]));
+        assert_eq!(PhredEncoding::Phred64.decode("!!"), None);
+        assert_eq!(PhredEncoding::Phred33.encode(&[0, 10, 20, 40]), Some("!+5I".to_string()));
+        assert_eq!(PhredEncoding::Phred64.encode(&[62]), Some("~".to_string()));
+        assert_eq!(PhredEncoding::Phred64.encode(&[0, 63]), None);
+        assert_eq!(PhredEncoding::Phred33.encode(&[255]), None);
+    }
+
+    #[test]
+    fn fastq_reader() {
+        use super::{FastqReader, PhredEncoding};
+        let input = "@read1 lane 1\nACGT\nAC\n+\n@@II\nII\n@read2\nGG\n+read2\n+5\n";
+        let records: Vec<_> = FastqReader::new(Cursor::new(input), PhredEncoding::Phred33)
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, "read1");
+        assert_eq!(records[0].description, Some("lane 1".to_string()));
+        assert_eq!(records[0].sequence, "ACGTAC");
+        assert_eq!(records[0].quality, vec![31, 31, 40, 40, 40, 40]);
+        assert_eq!(records[1].id, "read2");
+        assert_eq!(records[1].quality, vec![10, 20]);
+    }
+
+    #[test]
+    fn fastq_reader_errors() {
+        use super::{FastqError, FastqReader, PhredEncoding};
+        let mut reader = FastqReader::new(Cursor::new("@read1\nACGT\n+\nIII\n"), PhredEncoding::Phred33);
+        match reader.next() {
+            Some(Err(FastqError::LengthMismatch(ref id))) if id == "read1" => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert!(reader.next().is_none());
+
+        let mut reader = FastqReader::new(Cursor::new("ACGT\n"), PhredEncoding::Phred33);
+        match reader.next() {
+            Some(Err(FastqError::MissingHeader(1))) => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+
+        let mut reader = FastqReader::new(Cursor::new("@read1\nAC\n+\n!!\n"), PhredEncoding::Phred64);
+        match reader.next() {
+            Some(Err(FastqError::InvalidQuality(_))) => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn fastq_quality() {
+        let mut record = super::FastqRecord {
+            id: "read1".to_string(),
+            description: None,
+            sequence: "ACGTAC".to_string(),
+            quality: vec![30, 30, 20, 30, 5, 2],
+        };
+        assert_eq!(record.mean_quality(), 117.0 / 6.0);
+        record.trim_low_quality_tail(20);
+        assert_eq!(record.sequence, "ACGT");
+        assert_eq!(record.quality, vec![30, 30, 20, 30]);
+        record.trim_low_quality_tail(40);
+        assert_eq!(record.sequence, "");
+        assert_eq!(record.mean_quality(), 0.0);
+    }
+
+    #[test]
+    fn fasta_writer() {
+        use super::{FastaRecord, FastaWriter};
+        let record = FastaRecord {
+            id: "seq1".to_string(),
+            description: Some("revcomp".to_string()),
+            sequence: "ACGTACGTAC".to_string(),
+        };
+        let mut writer = FastaWriter::with_line_width(Vec::new(), 4);
+        writer.write_record(&record).unwrap();
+        writer.write("seq2", None, "GG").unwrap();
+        let out = writer.into_inner();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), ">seq1 revcomp\nACGT\nACGT\nAC\n>seq2\nGG\n");
+
+        let records: Vec<_> = FastaReader::new(Cursor::new(out)).map(|x| x.unwrap()).collect();
+        assert_eq!(records[0], record);
+
+        let mut writer = FastaWriter::with_line_width(Vec::new(), 0);
+        writer.write_record(&record).unwrap();
+        assert_eq!(writer.into_inner(), b">seq1 revcomp\nACGTACGTAC\n".to_vec());
+
+        let mut writer = FastaWriter::new(Vec::new());
+        assert!(writer.write("seq3", None, "").is_err());
+    }
+
+    #[test]
+    fn fastq_writer() {
+        use super::{FastqReader, FastqRecord, FastqWriter, PhredEncoding};
+        let record = FastqRecord {
+            id: "read1".to_string(),
+            description: None,
+            sequence: "ACGT".to_string(),
+            quality: vec![40, 30, 20, 10],
+        };
+        let mut writer = FastqWriter::new(Vec::new(), PhredEncoding::Phred33);
+        writer.write_record(&record).unwrap();
+        let out = writer.into_inner();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), "@read1\nACGT\n+\nI?5+\n");
+
+        let records: Vec<_> = FastqReader::new(Cursor::new(out), PhredEncoding::Phred33)
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(records, vec![record]);
+
+        let record = FastqRecord { quality: vec![70, 30, 20, 10], ..records[0].clone() };
+        let mut writer = FastqWriter::new(Vec::new(), PhredEncoding::Phred64);
+        assert!(writer.write_record(&record).is_err());
+    }
+
+    #[test]
+    fn fasta_index() {
+        use super::{FaiEntry, FastaIndex};
+        let index = FastaIndex::from_fasta_file("test.fa").unwrap();
+        assert_eq!(index.entries().len(), 3);
+        assert_eq!(*index.get("Rosalind_5959").unwrap(), FaiEntry {
+            name: "Rosalind_5959".to_string(),
+            length: 84,
+            offset: 112,
+            line_bases: 60,
+            line_width: 61,
+        });
+
+        let mut out = Vec::new();
+        index.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap().lines().next().unwrap(),
+                   "Rosalind_6404\t80\t15\t60\t61");
+        assert_eq!(FastaIndex::read(Cursor::new(out)).unwrap(), index);
+
+        match FastaIndex::build(Cursor::new(">seq1\nACG\nA\nACG\n")) {
+            Err(FastaError::InconsistentLineLength(ref id)) if id == "seq1" => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn indexed_fasta_fetch() {
+        use super::{FastaIndex, IndexedFastaReader};
+        let mut reader = IndexedFastaReader::from_file("test.fa").unwrap();
+        assert_eq!(reader.fetch("Rosalind_6404", 55, 65).unwrap(), "TTCCCTCCCA");
+        assert_eq!(reader.fetch("Rosalind_0808", 0, 5).unwrap(), "CCACC");
+        assert_eq!(reader.fetch_all("Rosalind_5959").unwrap(), "CCATCGGTAGCGCATCCTTAGTCCAATTAAGTCCCTATCCAGGCGCTCCGCCGAAGGTCTATATCCATTTGTCAGCAGACACGC");
+        assert!(reader.fetch("Rosalind_6404", 70, 81).is_err());
+        assert!(reader.fetch("Rosalind_0000", 0, 1).is_err());
+
+        let input = ">seq1\r\nACGT\r\nACGT\r\nAC\r\n>seq2\r\nTTTT\r\n";
+        let index = FastaIndex::build(Cursor::new(input)).unwrap();
+        let mut reader = IndexedFastaReader::new(Cursor::new(input), index);
+        assert_eq!(reader.fetch("seq1", 3, 9).unwrap(), "TACGTA");
+        assert_eq!(reader.fetch_all("seq2").unwrap(), "TTTT");
+
+        for input in &[">s\nACGT\nAC", ">s\nACGT\nACGT", ">s\r\nACGT\r\nAC"] {
+            let index = FastaIndex::build(Cursor::new(*input)).unwrap();
+            let length = index.get("s").unwrap().length as usize;
+            let mut reader = IndexedFastaReader::new(Cursor::new(*input), index);
+            assert_eq!(reader.fetch("s", 2, length).unwrap(), &"ACGTACGT"[2..length]);
+        }
+    }
+
+    #[test]
+    fn gzip_input() {
+        use std::io::Read;
+        let records: Vec<_> = FastaReader::from_file("test.fa.gz").unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        let expected: Vec<_> = FastaReader::from_file("test.fa").unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(records, expected);
+
+        let mut text = String::new();
+        super::decompress(Cursor::new(b"ACGT\n".to_vec())).unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "ACGT\n");
+
+        match super::IndexedFastaReader::from_file("test.fa.gz") {
+            Err(FastaError::CompressedInput(ref filename)) if filename == "test.fa.gz" => (),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("compressed input was indexed"),
+        }
+        assert!(super::FastaIndex::from_fasta_file("test.fa.gz").is_err());
     }
 }
@@ -1,190 +1,374 @@
 extern crate itertools;
 use self::itertools::Itertools;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::collections::hash_map::Entry;
 
-#[derive(Clone)]
-struct Product {
-    n: usize,
-    d: usize,
-    vals: Vec<usize>,
+use combinatorics::{Combinations, Product};
+use dna_utils::{iupac_bases, within_distance};
+use error::{check_k, Error, Result};
+use parallel::{default_threads, map_ranges};
+use sequence::Dna;
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// 2-bit code of a nucleotide, case-insensitive
+///
+fn base_code(base: u8) -> Option<u128> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
 }
 
-impl Product {
-    fn new(n: usize, d: usize) -> Product {
-        Product {
-            n: n,
-            d: d,
-            vals: Vec::new(),
+/// a kmer, packed 2 bits per base into an integer when it has at
+/// most `Kmer::MAX_K` bases and kept as uppercase bases otherwise,
+/// so kmers of the same length order lexicographically
+///
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Kmer {
+    repr: Repr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Repr {
+    Packed { k: u8, bits: u128 },
+    Bases(Box<[u8]>),
+}
+
+/// mask of the bits used by a packed kmer of length k
+///
+fn packed_mask(k: usize) -> u128 {
+    if k == Kmer::MAX_K { !0 } else { (1 << (2 * k)) - 1 }
+}
+
+/// slide a packed kmer of length k one base
+///
+fn roll_packed(bits: u128, code: u128, k: usize) -> u128 {
+    ((bits << 2) | code) & packed_mask(k)
+}
+
+/// complement of an uppercase ACGT base
+///
+fn complement_base(base: u8) -> u8 {
+    b"TGCA"[base_code(base).unwrap_or(0) as usize]
+}
+
+impl Kmer {
+    /// longest kmer that can be packed
+    ///
+    pub const MAX_K: usize = 64;
+
+    fn packed(k: usize, bits: u128) -> Kmer {
+        Kmer { repr: Repr::Packed { k: k as u8, bits } }
+    }
+
+    /// kmer from uppercase ACGT bases, packed if short enough
+    ///
+    fn from_valid(kmer: &[u8]) -> Kmer {
+        if kmer.len() > Kmer::MAX_K {
+            return Kmer { repr: Repr::Bases(kmer.into()) };
         }
+        let bits = kmer.iter()
+            .fold(0, |bits, &c| roll_packed(bits, base_code(c).unwrap_or(0), kmer.len()));
+        Kmer::packed(kmer.len(), bits)
+    }
+
+    /// make a kmer from its bases, lowercase bases are accepted
+    ///
+    pub fn from_bytes(kmer: &[u8]) -> Result<Kmer> {
+        if kmer.is_empty() { return Err(Error::EmptyKmer); }
+        check_bases(kmer)?;
+        Ok(Kmer::from_valid(&kmer.to_ascii_uppercase()))
     }
-}
 
-impl Iterator for Product {
-    type Item = Vec<usize>;
+    /// kmer of length k from its packed bases
+    ///
+    pub fn from_bits(bits: u128, k: usize) -> Result<Kmer> {
+        if k == 0 { return Err(Error::EmptyKmer); }
+        if k > Kmer::MAX_K { return Err(Error::KmerTooLarge(k, Kmer::MAX_K)); }
+        if bits & !packed_mask(k) != 0 { return Err(Error::InvalidKmerIndex(bits, k)); }
+        Ok(Kmer::packed(k, bits))
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.vals.len() == 0 {
-            for _ in 0..self.d {
-                self.vals.push(0);
-            }
-            return Some(self.vals.clone());
+    pub fn len(&self) -> usize {
+        match self.repr {
+            Repr::Packed { k, .. } => k as usize,
+            Repr::Bases(ref bases) => bases.len(),
         }
+    }
 
-        let mut cur_position = self.d - 1;
-        loop {
-            if self.vals[cur_position] < self.n - 1 {
-                self.vals[cur_position] += 1;
-                break;
-            }
-            if cur_position == 0 { return None };
-            cur_position -= 1;
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// packed bases, the last base in the lowest two bits, None for
+    /// kmers longer than `Kmer::MAX_K`
+    ///
+    pub fn bits(&self) -> Option<u128> {
+        match self.repr {
+            Repr::Packed { bits, .. } => Some(bits),
+            Repr::Bases(_) => None,
         }
-        cur_position += 1;
-        while cur_position < self.d {
-            self.vals[cur_position] = 0;
-            cur_position += 1;
+    }
+
+    /// slide the window one base, dropping the first base and
+    /// appending the given one
+    ///
+    pub fn roll(&self, base: u8) -> Option<Kmer> {
+        let code = base_code(base)?;
+        match self.repr {
+            Repr::Packed { k, bits } => Some(Kmer::packed(k as usize, roll_packed(bits, code, k as usize))),
+            Repr::Bases(ref bases) => {
+                let mut bases = bases[1..].to_vec();
+                bases.push(base.to_ascii_uppercase());
+                Some(Kmer { repr: Repr::Bases(bases.into()) })
+            },
         }
-        Some(self.vals.clone())
     }
-}
 
-struct Combinations {
-    n:    usize,
-    d:    usize,
-    vals: Vec<usize>,
-}
+    /// unpack the kmer into uppercase bases
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self.repr {
+            Repr::Packed { k, bits } => (0..k as usize).rev()
+                .map(|i| b"ACGT"[((bits >> (2 * i)) & 3) as usize])
+                .collect(),
+            Repr::Bases(ref bases) => bases.to_vec(),
+        }
+    }
 
-impl Combinations {
-    fn new(n: usize, d: usize) -> Combinations {
-        if d >= n {
-            panic!("Can't create these combinations");
+    /// reverse complement of the kmer
+    ///
+    pub fn revcomp(&self) -> Kmer {
+        match self.repr {
+            Repr::Packed { k, bits } => {
+                // complementing a 2-bit code is flipping both bits
+                let complement = !bits & packed_mask(k as usize);
+                // reversing the bits also reverses the bits inside each base
+                let reversed = complement.reverse_bits();
+                let pairs = 0x5555_5555_5555_5555_5555_5555_5555_5555u128;
+                let swapped = ((reversed >> 1) & pairs) | ((reversed & pairs) << 1);
+                Kmer::packed(k as usize, swapped >> (128 - 2 * k as usize))
+            },
+            Repr::Bases(ref bases) => {
+                let bases: Vec<u8> = bases.iter().rev().map(|&c| complement_base(c)).collect();
+                Kmer { repr: Repr::Bases(bases.into()) }
+            },
         }
+    }
+
+    /// the lexicographically smaller of the kmer and its reverse
+    /// complement
+    ///
+    pub fn canonical(&self) -> Kmer {
+        let revcomp = self.revcomp();
+        if revcomp < *self { revcomp } else { self.clone() }
+    }
 
-        Combinations {
-            n: n,
-            d: d,
-            vals: Vec::new(),
+    /// substitute the given positions, xor-ing a base code with 1, 2
+    /// or 3 turns it into each of the other three bases
+    ///
+    fn substitute(&self, positions: &[usize], substitutions: &[usize]) -> Kmer {
+        match self.repr {
+            Repr::Packed { k, mut bits } => {
+                for (position, substitution) in positions.iter().zip(substitutions) {
+                    let shift = 2 * (k as usize - 1 - position);
+                    bits ^= (*substitution as u128 + 1) << shift;
+                }
+                Kmer::packed(k as usize, bits)
+            },
+            Repr::Bases(ref bases) => {
+                let mut bases = bases.to_vec();
+                for (&position, substitution) in positions.iter().zip(substitutions) {
+                    let code = base_code(bases[position]).unwrap_or(0) ^ (*substitution as u128 + 1);
+                    bases[position] = b"ACGT"[code as usize];
+                }
+                Kmer { repr: Repr::Bases(bases.into()) }
+            },
         }
     }
 }
 
-impl Iterator for Combinations {
-    type Item = Vec<usize>;
+impl FromStr for Kmer {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Kmer> {
+        Kmer::from_bytes(s.as_bytes())
+    }
+}
+
+impl fmt::Display for Kmer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// rolling iterator over the kmers of a sequence, yields each kmer
+/// with its start position; windows with an IUPAC ambiguity code,
+/// such as N, are skipped
+///
+pub struct Kmers<'a> {
+    text: &'a [u8],
+    k: usize,
+    // rolling packed bases, only used for kmers that can be packed
+    bits: u128,
+    position: usize,
+    skip_masked: bool,
+    // position just past the last skipped base seen
+    clear_from: usize,
+}
+
+impl<'a> Kmers<'a> {
+    /// kmers of a sequence, upper and lowercase bases are read
+    /// the same
+    ///
+    pub fn new(text: &'a [u8], k: usize) -> Result<Kmers<'a>> {
+        if k == 0 { return Err(Error::EmptyKmer); }
+        check_iupac(text)?;
+        Ok(Kmers::with_masking(text, k, false))
+    }
+
+    /// kmers that don't overlap a soft-masked (lowercase) region
+    ///
+    pub fn unmasked(text: &'a [u8], k: usize) -> Result<Kmers<'a>> {
+        if k == 0 { return Err(Error::EmptyKmer); }
+        check_iupac(text)?;
+        Ok(Kmers::with_masking(text, k, true))
+    }
+
+    fn with_masking(text: &'a [u8], k: usize, skip_masked: bool) -> Kmers<'a> {
+        Kmers { text, k, bits: 0, position: 0, skip_masked, clear_from: 0 }
+    }
+}
+
+impl<'a> Iterator for Kmers<'a> {
+    type Item = (usize, Kmer);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // if value vector is empty, initialize
-        if self.vals.len() == 0 {
-            for i in 0..self.d {
-                self.vals.push(i);
+        let k = self.k;
+        while self.position < self.text.len() {
+            let c = self.text[self.position];
+            self.position += 1;
+            let code = base_code(c);
+            if code.is_none() || (self.skip_masked && c.is_ascii_lowercase()) {
+                self.clear_from = self.position;
             }
-            return Some(self.vals.clone());
-        }
-
-        // check if we can advance index from last to first position
-        let mut cur_position = self.d - 1;
-        loop {
-            if self.vals[cur_position] + (self.d - cur_position) < self. n {
-                // advance index in this column and break loop
-                self.vals[cur_position] += 1;
-                break;
+            if k <= Kmer::MAX_K {
+                self.bits = roll_packed(self.bits, code.unwrap_or(0), k);
             }
 
-            // if this is the first position, there are no more combinations
-            if cur_position == 0 { return None };
-            cur_position -= 1;
-        }
-
-        // we advanced some position, go from this position to the last to
-        // update the combination accordingly
-        cur_position += 1;
-        while cur_position < self.d {
-            self.vals[cur_position] = self.vals[cur_position - 1] + 1;
-            cur_position += 1;
-        }
-        // return resulting vector
-        Some(self.vals.clone())
-    }
-}
-
-struct KmerNeighborhood<'a> {
-    chars: &'a [u8],
-    n: usize,
-    buffer: Vec<u8>,
-    nucs: &'a [u8; 4],
-    index_iterator: itertools::Product<Combinations, Product>,
-}
-
-impl<'a> KmerNeighborhood<'a> {
-    fn new(kmer: &str, d: usize) -> KmerNeighborhood {
-        let chars = kmer.as_bytes();
-        let n = chars.len();
-        let mut buffer = Vec::with_capacity(n);
-        for i in 0..n { buffer.push(chars[i]); }
-        let combinations = combinations(n, d);
-        let product = product(4, d);
-        let index_iterator = combinations.cartesian_product(product);
-
-        KmerNeighborhood {
-            chars: chars,
-            n: n,
-            buffer: buffer,
-            nucs: b"ACGT",
-            index_iterator: index_iterator,
+            if self.position >= k && self.position - k >= self.clear_from {
+                let start = self.position - k;
+                let kmer = if k <= Kmer::MAX_K {
+                    Kmer::packed(k, self.bits)
+                } else {
+                    Kmer::from_valid(&self.text[start..self.position].to_ascii_uppercase())
+                };
+                return Some((start, kmer));
+            }
         }
+        None
     }
+}
 
-    fn build_string(&mut self, ivec: &Vec<usize>, jvec: &Vec<usize>) -> Vec<u8> {
-        for i in 0..self.n { self.buffer[i] = self.chars[i] };
+/// iterator over the kmers within Hamming distance d of a kmer,
+/// each neighbor is produced once, in order of increasing distance
+///
+pub struct Neighbors {
+    kmer: Kmer,
+    d: usize,
+    distance: usize,
+    // positions to substitute and which of the other three bases
+    // to put at each of them
+    positions: Combinations,
+    substitutions: Product,
+}
 
-        for (i,j) in ivec.iter().zip(jvec) {
-            self.buffer[*i] = self.nucs[*j];
+impl Neighbors {
+    fn new(kmer: &Kmer, d: usize) -> Neighbors {
+        Neighbors {
+            kmer: kmer.clone(),
+            // every position can be substituted at most once
+            d: d.min(kmer.len()),
+            distance: 0,
+            positions: Combinations::new(kmer.len(), 0),
+            // nothing to substitute until the first positions are chosen
+            substitutions: Product::new(0, 1),
         }
-        self.buffer.clone()
     }
+
 }
 
-impl<'a> Iterator for KmerNeighborhood<'a> {
-    type Item = Vec<u8>;
+impl Iterator for Neighbors {
+    type Item = Kmer;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let indices: Option<(Vec<usize>, Vec<usize>)> = self.index_iterator.next();
-        match indices {
-            None => None,
-            Some((ivec, jvec)) => Some(self.build_string(&ivec, &jvec)),
+        loop {
+            if self.substitutions.advance() {
+                return Some(self.kmer.substitute(self.positions.current(), self.substitutions.current()));
+            }
+
+            if self.positions.advance() {
+                self.substitutions = Product::new(3, self.distance);
+            } else {
+                if self.distance == self.d { return None; }
+                self.distance += 1;
+                self.positions = Combinations::new(self.kmer.len(), self.distance);
+                self.substitutions = Product::new(0, 1);
+            }
         }
     }
 }
 
-/// generate combinations
+/// kmers within Hamming distance d of a kmer, including the kmer
+/// itself
 ///
-fn combinations(n: usize, d: usize) -> Combinations {
-    Combinations::new(n,d)
+pub fn neighborhood(kmer: &Kmer, d: usize) -> Neighbors {
+    Neighbors::new(kmer, d)
 }
 
-/// generate cartesian Product
-fn product(n: usize, d: usize) -> Product {
-    Product::new(n, d)
+/// the kmer and every kmer that differs from it at a single position
+///
+pub fn immediate_neighbors(kmer: &Kmer) -> Vec<Kmer> {
+    neighborhood(kmer, 1).collect()
 }
 
-/// generate string neighborhood
+/// check that text only has ACGT bases, in either case
 ///
-fn neighborhood(kmer: &str, d: usize) -> HashSet<Vec<u8>> {
-    let kmer_neighborhood = KmerNeighborhood::new(kmer, d);
-    let mut res = HashSet::new();
-    for kmer in kmer_neighborhood {
-        res.insert(kmer);
+fn check_bases(text: &[u8]) -> Result<()> {
+    match text.iter().position(|&c| base_code(c).is_none()) {
+        Some(i) => Err(Error::InvalidCharacter(text[i] as char, i)),
+        None => Ok(()),
     }
-    res
 }
 
-use std::thread;
-use std::sync::{Arc, Mutex};
+/// check that text only has IUPAC nucleotide codes, in either case
+///
+fn check_iupac(text: &[u8]) -> Result<()> {
+    match text.iter().position(|&c| iupac_bases(c).is_none()) {
+        Some(i) => Err(Error::InvalidCharacter(text[i] as char, i)),
+        None => Ok(()),
+    }
+}
+
+/// order kmer counts by decreasing count, ties in lexicographic order
+///
+fn by_count(left: &(Kmer, i32), right: &(Kmer, i32)) -> Ordering {
+    right.1.cmp(&left.1).then_with(|| left.0.cmp(&right.0))
+}
 
 #[derive(Clone)]
 pub struct KmerCounter {
-    map: HashMap<Vec<u8>, i32>,
+    map: HashMap<Kmer, i32>,
 }
 
 impl KmerCounter {
@@ -192,93 +376,188 @@ impl KmerCounter {
         KmerCounter { map: HashMap::new() }
     }
 
-    fn count(&mut self, kmer: &str) {
-        let kmer = kmer.bytes().collect();
-        self.count_u8(&kmer);
-    }
-
-    fn count_u8(&mut self, kmer: &Vec<u8>) {
-        let counter = self.map.entry(kmer.clone()).or_insert(0i32);
-        *counter += 1;
+    pub fn insert(&mut self, kmer: Kmer, val: i32) {
+        let counter = self.map.entry(kmer).or_insert(0i32);
+        *counter += val;
     }
 
-    fn insert(&mut self, kmer: &str, val: i32) {
-        let kmer = kmer.bytes().collect();
-        self.insert_u8(&kmer, val);
+    pub fn insert_u8(&mut self, kmer: &[u8], val: i32) -> Result<()> {
+        self.insert(Kmer::from_bytes(kmer)?, val);
+        Ok(())
     }
 
-    pub fn insert_u8(&mut self, kmer: &Vec<u8>, val: i32) {
-        let counter = self.map.entry(kmer.clone()).or_insert(0i32);
-        *counter += val;
+    /// add the counts of another counter to this one
+    ///
+    pub fn merge(&mut self, other: KmerCounter) {
+        for (kmer, count) in other.map {
+            self.insert(kmer, count);
+        }
     }
 
-    pub fn to_hashmap(&self) -> &HashMap<Vec<u8>, i32> {
+    pub fn to_hashmap(&self) -> &HashMap<Kmer, i32> {
         &self.map
     }
 
-    /// get frequent kmers
+    /// get frequent kmers, in lexicographic order
     ///
     pub fn find_frequent_kmers(&self) ->  Vec<String> {
-        let mut res: Vec<String> = Vec::new();
-        let mut highest_count: i32 = 0;
-
-        for (kmer, count) in &self.map {
-            if *count > highest_count {
-                highest_count = count.clone();
-                res.clear();
-                let s = String::from_utf8(kmer.clone()).unwrap();
-                res.push(s);
-            } else if *count == highest_count {
-                let s = String::from_utf8(kmer.clone()).unwrap();
-                res.push(s);
-            }
+        let highest_count = self.map.values().cloned().max().unwrap_or(0);
+        self.with_min_count(highest_count).iter()
+            .map(|(kmer, _)| kmer.to_string())
+            .collect()
+    }
+
+    /// every kmer with its count, most frequent first and ties in
+    /// lexicographic order
+    ///
+    pub fn sorted_counts(&self) -> Vec<(Kmer, i32)> {
+        let mut res: Vec<(Kmer, i32)> = self.map.iter().map(|(kmer, count)| (kmer.clone(), *count)).collect();
+        res.sort_by(by_count);
+        res
+    }
+
+    /// the n most frequent kmers with their counts, sorted as in
+    /// `sorted_counts`, ties at the cutoff are broken lexicographically
+    ///
+    pub fn top_n(&self, n: usize) -> Vec<(Kmer, i32)> {
+        let mut res: Vec<(Kmer, i32)> = self.map.iter().map(|(kmer, count)| (kmer.clone(), *count)).collect();
+        if n < res.len() {
+            res.select_nth_unstable_by(n, by_count);
+            res.truncate(n);
+        }
+        res.sort_by(by_count);
+        res
+    }
+
+    /// kmers seen at least min_count times with their counts, sorted
+    /// as in `sorted_counts`
+    ///
+    pub fn with_min_count(&self, min_count: i32) -> Vec<(Kmer, i32)> {
+        let mut res: Vec<(Kmer, i32)> = self.map.iter()
+            .filter(|&(_, count)| *count >= min_count)
+            .map(|(kmer, count)| (kmer.clone(), *count))
+            .collect();
+        res.sort_by(by_count);
+        res
+    }
+
+    /// kmer abundance histogram, the number of distinct kmers seen
+    /// each number of times
+    ///
+    pub fn histogram(&self) -> BTreeMap<i32, usize> {
+        let mut res = BTreeMap::new();
+        for count in self.map.values() {
+            *res.entry(*count).or_insert(0) += 1;
         }
         res
     }
 }
 
-/// count kmers
+/// count kmers, upper and lowercase bases are counted together,
+/// windows with an ambiguity code, such as N, are skipped and any
+/// other character is an error
 ///
-pub fn count_kmers<'a>(dna: &'a str, k: usize) -> KmerCounter {
-    let kmer_counts: Arc<Mutex<KmerCounter>> = Arc::new(Mutex::new(KmerCounter::new()));
-    let dna = dna.to_string();
+pub fn count_kmers(dna: &str, k: usize) -> Result<KmerCounter> {
+    count_kmers_threaded(dna, k, default_threads())
+}
 
-    let n = dna.len();
-    let handles: Vec<_> = (0..n-k+1).map(|start| {
-        let dna = dna.clone();
-        let kmer_counts = kmer_counts.clone();
+/// count kmers in a validated DNA sequence
+///
+pub fn count_kmers_dna(dna: &Dna, k: usize) -> Result<KmerCounter> {
+    count_kmers(dna.as_str(), k)
+}
+
+/// count kmers using the given number of threads
+///
+pub fn count_kmers_threaded(dna: &str, k: usize, threads: usize) -> Result<KmerCounter> {
+    count_kmers_at(dna.as_bytes(), k, threads, false, forward)
+}
+
+/// count canonical kmers, so a kmer and its reverse complement
+/// are counted together
+///
+pub fn count_kmers_canonical(dna: &str, k: usize) -> Result<KmerCounter> {
+    count_kmers_at(dna.as_bytes(), k, default_threads(), false, Kmer::canonical)
+}
 
-        thread::spawn(move || {
-            let kmer = &dna[start..start+k];
-            let mut kmer_counts = kmer_counts.lock().unwrap();
-            kmer_counts.count(kmer);
-        })
-    }).collect();
+/// count kmers, skipping those that overlap soft-masked
+/// (lowercase) regions
+///
+pub fn count_kmers_unmasked(dna: &str, k: usize) -> Result<KmerCounter> {
+    count_kmers_at(dna.as_bytes(), k, default_threads(), true, forward)
+}
+
+/// kmers as read on the forward strand
+///
+fn forward(kmer: &Kmer) -> Kmer {
+    kmer.clone()
+}
+
+/// count the kmers of a sequence under the given key
+///
+fn count_kmers_serial(dna: &[u8], k: usize, skip_masked: bool, key: fn(&Kmer) -> Kmer) -> KmerCounter {
+    let mut kmer_counts = KmerCounter::new();
+    for (_, kmer) in Kmers::with_masking(dna, k, skip_masked) {
+        kmer_counts.insert(key(&kmer), 1);
+    }
+    kmer_counts
+}
 
-    for h in handles { h.join().unwrap(); }
-    let ref kmer_counts = *kmer_counts.lock().unwrap();
-    kmer_counts.clone()
+/// count kmers splitting the start positions into one chunk per
+/// thread; neighbouring chunks overlap by k-1 bases of sequence,
+/// each thread counts into its own counter and the others are
+/// merged into the largest one at the end
+///
+fn count_kmers_at(dna: &[u8], k: usize, threads: usize, skip_masked: bool, key: fn(&Kmer) -> Kmer) -> Result<KmerCounter> {
+    let n = dna.len();
+    check_k(k, n)?;
+    check_iupac(dna)?;
+
+    let starts = n - k + 1;
+    let mut chunks = map_ranges(starts, threads, |range| {
+        count_kmers_serial(&dna[range.start..range.end+k-1], k, skip_masked, key)
+    })?;
+
+    // merge into the largest counter so its kmers aren't hashed again,
+    // there is always at least one chunk
+    let largest = (0..chunks.len()).max_by_key(|&i| chunks[i].map.len()).unwrap_or(0);
+    let mut kmer_counts = chunks.swap_remove(largest);
+    for counts in chunks {
+        kmer_counts.merge(counts);
+    }
+    Ok(kmer_counts)
 }
 
 /// count kmers with mismatches
 ///
-pub fn count_mismatch_kmers(text: &str, k: usize, d:usize) -> KmerCounter {
+pub fn count_mismatch_kmers(text: &str, k: usize, d:usize) -> Result<KmerCounter> {
+    count_mismatch_kmers_by(text, k, d, forward)
+}
+
+/// count canonical kmers with mismatches, so approximate
+/// occurrences of a kmer and its reverse complement are counted
+/// together
+///
+pub fn count_mismatch_kmers_canonical(text: &str, k: usize, d:usize) -> Result<KmerCounter> {
+    count_mismatch_kmers_by(text, k, d, Kmer::canonical)
+}
+
+fn count_mismatch_kmers_by(text: &str, k: usize, d:usize, key: fn(&Kmer) -> Kmer) -> Result<KmerCounter> {
     let mut kmer_counts = KmerCounter::new();
+    check_k(k, text.len())?;
 
-    let n = text.len();
-    for start in 0..n-k+1 {
-        let kmer = &text[start..start+k];
-        let kmer_neighborhood = neighborhood(kmer, d);
-        for approximate_kmer in kmer_neighborhood.iter() {
-            kmer_counts.count_u8(approximate_kmer);
+    for (_, kmer) in Kmers::new(text.as_bytes(), k)? {
+        for approximate_kmer in neighborhood(&kmer, d) {
+            kmer_counts.insert(key(&approximate_kmer), 1);
         }
     }
-    kmer_counts
+    Ok(kmer_counts)
 }
 
 /// kmer locator
+#[derive(Default)]
 pub struct KmerLocator {
-    map: HashMap<Vec<u8>, Vec<usize>>,
+    map: HashMap<Kmer, Vec<usize>>,
 }
 
 impl KmerLocator {
@@ -286,26 +565,36 @@ impl KmerLocator {
         KmerLocator { map: HashMap::new() }
     }
 
-    pub fn insert(&mut self, kmer: &str, location: usize) {
-        let kmer = kmer.bytes().collect();
-        let locations = self.map.entry(kmer).or_insert(Vec::new());
+    pub fn insert(&mut self, kmer: &str, location: usize) -> Result<()> {
+        self.insert_kmer(kmer.parse()?, location);
+        Ok(())
+    }
+
+    fn insert_kmer(&mut self, kmer: Kmer, location: usize) {
+        let locations = self.map.entry(kmer).or_default();
         locations.push(location)
     }
 
-    pub fn insert_locations(&mut self, kmer: &str, locations: Vec<usize>) {
-        let kmer = kmer.bytes().collect();
-        let kmer_locations = self.map.entry(kmer).or_insert(Vec::new());
+    pub fn insert_locations(&mut self, kmer: &str, locations: Vec<usize>) -> Result<()> {
+        let kmer_locations = self.map.entry(kmer.parse()?).or_default();
 
         for location in &locations {
             kmer_locations.push(*location);
         }
+        Ok(())
     }
 
-    pub fn to_hashmap(&self) -> &HashMap<Vec<u8>, Vec<usize>> {
+    pub fn to_hashmap(&self) -> &HashMap<Kmer, Vec<usize>> {
         &self.map
     }
 
-    pub fn find_clumps(&self, l: usize, t: usize, k:usize) -> Vec<String> {
+    /// kmers forming clumps, from the locations of every kmer
+    ///
+    pub fn find_clumps(&self, params: &ClumpParams) -> Result<Vec<String>> {
+        let ClumpParams { k, l, t } = *params;
+        check_k(k, l)?;
+        // any kmer that occurs at all forms a clump when t is 0
+        let t = t.max(1);
         let mut clumps = Vec::new();
 
         for (kmer, locations) in &self.map {
@@ -313,143 +602,608 @@ impl KmerLocator {
             if n < t { continue; };
             for i in 0..n-t+1 {
                 if locations[i] + l - k >= locations[i+t-1] {
-                    let s = String::from_utf8(kmer.clone()).unwrap();
-                    clumps.push(s);
+                    clumps.push(kmer.to_string());
                     break;
                 }
             }
         }
-        clumps
+        Ok(clumps)
     }
 }
 
+/// parameters of a clump search, a kmer of length `k` forms a
+/// clump when it occurs at least `t` times within a window of
+/// length `l`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClumpParams {
+    pub k: usize,
+    pub l: usize,
+    pub t: usize,
+}
+
+/// a region where a kmer forms a clump, start and end are 0-based,
+/// half-open coordinates spanning the clumped occurrences
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clump {
+    pub kmer: Kmer,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// find clumps in a single pass, sliding a window of length l
+/// across the genome and keeping only the occurrences and clumps
+/// that reach into it; overlapping clumps of the same kmer are
+/// merged into one region
+///
+pub fn find_clumps(genome: &str, params: &ClumpParams) -> Result<Vec<Clump>> {
+    let ClumpParams { k, l, t } = *params;
+    check_k(k, l)?;
+    check_k(k, genome.len())?;
+    // any kmer that occurs at all forms a clump when t is 0
+    let t = t.max(1);
+
+    // kmers starting in the window, and where each of them starts;
+    // windows with ambiguity codes are skipped, so the window is kept
+    // by position rather than by number of kmers
+    let span = l - k;
+    let mut window: VecDeque<(usize, Kmer)> = VecDeque::with_capacity(span + 1);
+    let mut occurrences: HashMap<Kmer, VecDeque<usize>> = HashMap::new();
+    // the clump of each kmer that may still grow, and the end each
+    // open clump had when it was last extended; ends only grow with
+    // position so the queue is sorted
+    let mut open: HashMap<Kmer, Clump> = HashMap::new();
+    let mut ends: VecDeque<(usize, Kmer)> = VecDeque::new();
+    let mut clumps = Vec::new();
+
+    for (position, kmer) in Kmers::new(genome.as_bytes(), k)? {
+        // a clump ending before the window can't be extended, any new
+        // clump starts inside the window
+        let window_start = position.saturating_sub(span);
+        while ends.front().is_some_and(|&(end, _)| end < window_start) {
+            if let Some((end, old)) = ends.pop_front() {
+                // skip entries for clumps extended since
+                if open.get(&old).is_some_and(|clump| clump.end == end) {
+                    clumps.extend(open.remove(&old));
+                }
+            }
+        }
+
+        while window.front().is_some_and(|&(start, _)| start + span < position) {
+            if let Some((_, old)) = window.pop_front() {
+                if let Entry::Occupied(mut entry) = occurrences.entry(old) {
+                    entry.get_mut().pop_front();
+                    if entry.get().is_empty() { entry.remove(); }
+                }
+            }
+        }
+        window.push_back((position, kmer.clone()));
+
+        let positions = occurrences.entry(kmer.clone()).or_default();
+        positions.push_back(position);
+        if positions.len() < t { continue; }
+
+        let start = positions[positions.len() - t];
+        let end = position + k;
+        ends.push_back((end, kmer.clone()));
+        match open.get_mut(&kmer) {
+            Some(clump) if start <= clump.end => clump.end = end,
+            _ => {
+                if let Some(clump) = open.insert(kmer.clone(), Clump { kmer, start, end }) {
+                    clumps.push(clump);
+                }
+            }
+        }
+    }
+
+    clumps.extend(open.into_values());
+    clumps.sort_by(|a, b| (a.start, &a.kmer).cmp(&(b.start, &b.kmer)));
+    Ok(clumps)
+}
+
 /// locate kmer locator
 ///
-pub fn locate_kmers(genome: &str, k: usize) -> KmerLocator {
+pub fn locate_kmers(genome: &str, k: usize) -> Result<KmerLocator> {
+    check_k(k, genome.len())?;
+    Ok(locate_kmers_at(Kmers::new(genome.as_bytes(), k)?, forward))
+}
+
+/// locate canonical kmers, so occurrences of a kmer and its
+/// reverse complement share the same locations
+///
+pub fn locate_kmers_canonical(genome: &str, k: usize) -> Result<KmerLocator> {
+    check_k(k, genome.len())?;
+    Ok(locate_kmers_at(Kmers::new(genome.as_bytes(), k)?, Kmer::canonical))
+}
+
+/// locate kmers, skipping those that overlap soft-masked
+/// (lowercase) regions
+///
+pub fn locate_kmers_unmasked(genome: &str, k: usize) -> Result<KmerLocator> {
+    check_k(k, genome.len())?;
+    Ok(locate_kmers_at(Kmers::unmasked(genome.as_bytes(), k)?, forward))
+}
+
+fn locate_kmers_at(kmers: Kmers, key: fn(&Kmer) -> Kmer) -> KmerLocator {
     let mut kmer_locations = KmerLocator::new();
-    let n = genome.len();
 
-    for location in 0..n-k+1 {
-        let kmer = &genome[location..location+k];
-        kmer_locations.insert(kmer, location)
+    for (location, kmer) in kmers {
+        kmer_locations.insert_kmer(key(&kmer), location)
     }
     kmer_locations
 }
 
-/// find approximate matches
+/// how approximate matches are found
 ///
-pub fn find_matches(pattern: &str, text: &str, d: usize) -> Vec<usize> {
-    let neighborhood = neighborhood(pattern, d);
-    let text = text.as_bytes();
+/// `Neighborhood` looks up every text kmer in the d-neighborhood of
+/// the pattern, `Scan` compares every text window with the pattern
+/// and stops at the first mismatch past d
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStrategy {
+    Neighborhood,
+    Scan,
+}
+
+/// number of kmers within Hamming distance d of a kmer of length k
+///
+fn neighborhood_size(k: usize, d: usize) -> u128 {
+    let mut size: u128 = 0;
+    // C(k, j) * 3^j for each distance j
+    let mut term: u128 = 1;
+    for j in 0..d.min(k) + 1 {
+        size = size.saturating_add(term);
+        term = term.saturating_mul(3 * (k - j) as u128) / (j as u128 + 1);
+    }
+    size
+}
+
+/// pick a strategy for a pattern of length k in a text of length
+/// n, the neighborhood is used when the pattern can be packed and
+/// its neighborhood is no larger than the number of text windows
+///
+pub fn choose_strategy(k: usize, d: usize, n: usize) -> MatchStrategy {
+    let windows = n.saturating_sub(k) + 1;
+    if k <= Kmer::MAX_K && neighborhood_size(k, d) <= windows as u128 {
+        MatchStrategy::Neighborhood
+    } else {
+        MatchStrategy::Scan
+    }
+}
+
+/// call f with the start of every approximate match
+///
+fn for_each_match<F>(pattern: &str, text: &str, d: usize, strategy: MatchStrategy, mut f: F) -> Result<()>
+    where F: FnMut(usize)
+{
     let k = pattern.len();
-    let n = text.len();
+    check_k(k, text.len())?;
+    check_bases(pattern.as_bytes())?;
+    check_bases(text.as_bytes())?;
+
+    match strategy {
+        MatchStrategy::Neighborhood => {
+            let neighborhood: HashSet<Kmer> = neighborhood(&pattern.parse()?, d).collect();
+            for (i, kmer) in Kmers::new(text.as_bytes(), k)? {
+                if neighborhood.contains(&kmer) { f(i); }
+            }
+        },
+        MatchStrategy::Scan => {
+            let pattern = pattern.to_ascii_uppercase();
+            let text = text.to_ascii_uppercase();
+            let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+            for i in 0..text.len()-k+1 {
+                if within_distance(pattern, &text[i..i+k], d) { f(i); }
+            }
+        },
+    }
+    Ok(())
+}
+
+/// find approximate matches, choosing the strategy from the pattern
+/// length, d and the text length
+///
+pub fn find_matches(pattern: &str, text: &str, d: usize) -> Result<Vec<usize>> {
+    find_matches_with(pattern, text, d, choose_strategy(pattern.len(), d, text.len()))
+}
+
+/// find approximate matches with the given strategy
+///
+pub fn find_matches_with(pattern: &str, text: &str, d: usize, strategy: MatchStrategy) -> Result<Vec<usize>> {
     let mut result = Vec::new();
+    for_each_match(pattern, text, d, strategy, |i| result.push(i))?;
+    Ok(result)
+}
 
-    for i in 0..n-k+1 {
-        let kmer = &text[i..i+k];
-        if neighborhood.contains(kmer) {
-            result.push(i);
-        }
-    }
-    result
+/// count approximate matches without collecting their positions
+///
+pub fn approximate_pattern_count(pattern: &str, text: &str, d: usize) -> Result<usize> {
+    let mut count = 0;
+    let strategy = choose_strategy(pattern.len(), d, text.len());
+    for_each_match(pattern, text, d, strategy, |_| count += 1)?;
+    Ok(count)
 }
 
-/// kmer composition
+/// kmer composition, sorted lexicographically
 ///
-pub fn kmer_composition(text: &str, k: usize) -> Vec<Vec<u8>> {
+pub fn kmer_composition(text: &str, k: usize) -> Result<Vec<Vec<u8>>> {
     let n = text.len();
     let text = text.as_bytes();
-    let mut kmers = Vec::new();
+    check_k(k, n)?;
+    check_bases(text)?;
+
+    let mut kmers: Vec<Kmer> = Kmers::new(text, k)?.map(|(_, kmer)| kmer).collect();
+    kmers.sort();
+    Ok(kmers.iter().map(|kmer| kmer.to_bytes()).collect())
+}
+
+/// index of a kmer in the lexicographic order of all kmers of
+/// its length
+///
+pub fn pattern_to_number(pattern: &str) -> Result<u128> {
+    let kmer: Kmer = pattern.parse()?;
+    kmer.bits().ok_or(Error::KmerTooLarge(kmer.len(), Kmer::MAX_K))
+}
+
+/// kmer of length k at the given index in lexicographic order
+///
+pub fn number_to_pattern(index: u128, k: usize) -> Result<String> {
+    Ok(Kmer::from_bits(index, k)?.to_string())
+}
+
+/// longest kmer counted in a dense frequency array, the array has
+/// 4^k entries so k = 12 already takes 64 MiB of counts; longer
+/// kmers should be counted sparsely with `count_kmers`
+///
+pub const MAX_FREQUENCY_ARRAY_K: usize = 12;
 
-    for i in 0..n-k+1 {
-        let kmer = text[i..i+k].to_vec();
-        kmers.push(kmer);
+/// dense frequency array, the count of every kmer of length k
+/// indexed by `pattern_to_number`
+///
+pub fn computing_frequencies(text: &str, k: usize) -> Result<Vec<i32>> {
+    check_k(k, text.len())?;
+    if k > MAX_FREQUENCY_ARRAY_K { return Err(Error::KmerTooLarge(k, MAX_FREQUENCY_ARRAY_K)); }
+    let mut frequencies = vec![0; 1 << (2 * k)];
+
+    for (_, kmer) in Kmers::new(text.as_bytes(), k)? {
+        if let Some(bits) = kmer.bits() { frequencies[bits as usize] += 1; }
     }
+    Ok(frequencies)
+}
+
+/// most frequent kmers found by sorting the kmer indices and
+/// counting runs of equal indices, in lexicographic order
+///
+pub fn frequent_words_by_sorting(text: &str, k: usize) -> Result<Vec<String>> {
+    check_k(k, text.len())?;
+    let mut kmers: Vec<Kmer> = Kmers::new(text.as_bytes(), k)?.map(|(_, kmer)| kmer).collect();
     kmers.sort();
-    kmers
+
+    let mut res = Vec::new();
+    let mut highest_count = 0;
+    for (kmer, run) in kmers.iter().group_by(|kmer| (*kmer).clone()) {
+        let count = run.len();
+        if count > highest_count {
+            highest_count = count;
+            res.clear();
+        }
+        if count == highest_count {
+            res.push(kmer.to_string());
+        }
+    }
+    Ok(res)
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
     use dna_utils::num_mismatches;
+    use super::{Kmer, MatchStrategy};
+
+    fn kmer(s: &str) -> Kmer {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn packed_kmers() {
+        let packed = kmer("ACGT");
+        assert_eq!(packed.len(), 4);
+        assert_eq!(packed.bits(), Some(0b00_01_10_11));
+        assert_eq!(packed.to_string(), "ACGT");
+        assert_eq!(kmer("acgt"), packed);
+        assert_eq!(packed.roll(b'A').unwrap(), kmer("CGTA"));
+        assert!(packed.roll(b'N').is_none());
+        assert!(kmer("ACGT") < kmer("ACTA"));
+
+        let long = "T".repeat(Kmer::MAX_K);
+        assert_eq!(kmer(&long).to_string(), long);
+        assert_eq!(kmer(&long).roll(b'A').unwrap().to_string(), "T".repeat(63) + "A");
+        assert!("".parse::<Kmer>().is_err());
+        assert!("ACNT".parse::<Kmer>().is_err());
+    }
+
+    #[test]
+    fn long_kmers() {
+        let bases = "ACGGT".repeat(14);
+        let long = kmer(&bases.to_lowercase());
+        assert_eq!(long.len(), 70);
+        assert_eq!(long.to_string(), bases);
+        assert!(long.bits().is_none());
+        assert_eq!(long.roll(b'a').unwrap().to_string(), bases[1..].to_string() + "A");
+        assert!(long.roll(b'N').is_none());
+        assert_eq!(long.revcomp().to_string(), "ACCGT".repeat(14));
+        assert_eq!(long.canonical(), long.revcomp());
+        assert!(kmer(&"A".repeat(70)) < kmer(&("A".repeat(69) + "C")));
+        assert_eq!(super::neighborhood(&long, 1).count(), 1 + 3 * 70);
+        assert!(super::neighborhood(&long, 1)
+                .all(|x| num_mismatches(bases.as_bytes(), &x.to_bytes()) <= 1));
+
+        let genome = format!("{}TT{}", bases, bases);
+        let counts = super::count_kmers(&genome, 70).unwrap();
+        assert_eq!(counts.to_hashmap()[&long], 2);
+        assert_eq!(counts.to_hashmap().len(), 72);
+        let locations = super::locate_kmers(&genome, 70).unwrap();
+        assert_eq!(locations.to_hashmap()[&long], vec![0, 72]);
+
+        let mut locator = super::KmerLocator::new();
+        locator.insert(&bases, 3).unwrap();
+        assert_eq!(locator.to_hashmap()[&long], vec![3]);
+        assert_eq!(super::count_mismatch_kmers(&bases, 70, 1).unwrap().to_hashmap().len(), 211);
+    }
+
+    #[test]
+    fn ambiguous_bases_are_skipped() {
+        let kmers: Vec<_> = super::Kmers::new(b"ACNTAGNNACG", 2).unwrap().collect();
+        assert_eq!(kmers, vec![(0, kmer("AC")), (3, kmer("TA")), (4, kmer("AG")),
+                               (8, kmer("AC")), (9, kmer("CG"))]);
+
+        let genome = "ACGTNNNNACGTRACGT";
+        let counts = super::count_kmers(genome, 4).unwrap();
+        assert_eq!(counts.to_hashmap()[&kmer("ACGT")], 3);
+        assert_eq!(counts.to_hashmap().len(), 1);
+        for threads in 1..5 {
+            let threaded = super::count_kmers_threaded(genome, 4, threads).unwrap();
+            assert_eq!(threaded.to_hashmap(), counts.to_hashmap());
+        }
+        assert_eq!(super::locate_kmers(genome, 4).unwrap().to_hashmap()[&kmer("ACGT")], vec![0, 8, 13]);
+        assert_eq!(super::count_mismatch_kmers("ACGN", 2, 0).unwrap().to_hashmap().len(), 2);
+        assert!(super::count_kmers("NNNN", 2).unwrap().to_hashmap().is_empty());
+
+        let params = super::ClumpParams { k: 2, l: 6, t: 2 };
+        let clumps = super::find_clumps("ACNNACAC", &params).unwrap();
+        let regions: Vec<_> = clumps.iter().map(|x| (x.kmer.to_string(), x.start, x.end)).collect();
+        assert_eq!(regions, vec![("AC".to_string(), 0, 8)]);
+    }
+
+    #[test]
+    fn invalid_characters_are_rejected() {
+        assert!(matches!(super::count_kmers("hello world", 2), Err(super::Error::InvalidCharacter('e', 1))));
+        assert!(matches!(super::count_kmers("AC!G>T12", 2), Err(super::Error::InvalidCharacter('!', 2))));
+        assert!(matches!(super::count_kmers_threaded("ACGTACGTAC-", 2, 4), Err(super::Error::InvalidCharacter('-', 10))));
+        assert!(super::locate_kmers("ACGT ACGT", 4).is_err());
+        assert!(super::count_mismatch_kmers("AC*G", 2, 1).is_err());
+        assert!(super::find_clumps("ACAC.ACAC", &super::ClumpParams { k: 2, l: 6, t: 2 }).is_err());
+    }
+
+    #[test]
+    fn canonical_kmers() {
+        assert_eq!(kmer("AACG").revcomp(), kmer("CGTT"));
+        assert_eq!(kmer("CGTT").canonical(), kmer("AACG"));
+        assert_eq!(kmer("AACG").canonical(), kmer("AACG"));
+        assert_eq!(kmer("ACGT").revcomp(), kmer("ACGT"));
+        let long = "ACGGT".repeat(12) + "TGCA";
+        assert_eq!(kmer(&long).revcomp().revcomp(), kmer(&long));
+        assert_eq!(kmer(&long).revcomp().to_string().as_bytes()[..4], *b"TGCA");
+
+        let counts = super::count_kmers_canonical("AACGTT", 4).unwrap();
+        let counts = counts.to_hashmap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&kmer("AACG")], 2);
+        assert_eq!(counts[&kmer("ACGT")], 1);
+
+        let locations = super::locate_kmers_canonical("AACGTT", 4).unwrap();
+        assert_eq!(locations.to_hashmap()[&kmer("AACG")], vec![0, 2]);
+    }
+
+    #[test]
+    fn count_mismatch_kmers_canonical() {
+        let text = "ACGTTGCATGTCGCATGATGCATGAGAGCT";
+        let counts = super::count_mismatch_kmers(text, 4, 1).unwrap();
+        let canonical = super::count_mismatch_kmers_canonical(text, 4, 1).unwrap();
+        let counts = counts.to_hashmap();
+        for (kmer, count) in canonical.to_hashmap() {
+            let forward = counts.get(kmer).cloned().unwrap_or(0);
+            let reverse = counts.get(&kmer.revcomp()).cloned().unwrap_or(0);
+            let expected = if *kmer == kmer.revcomp() { forward } else { forward + reverse };
+            assert_eq!(*count, expected);
+        }
+        // ATGT and its reverse complement ACAT are the most frequent
+        assert_eq!(canonical.find_frequent_kmers(), vec!["ACAT"]);
+    }
+
+    #[test]
+    fn pattern_numbers() {
+        assert_eq!(super::pattern_to_number("AGT").unwrap(), 11);
+        assert!(super::pattern_to_number("").is_err());
+        assert_eq!(super::number_to_pattern(45, 4).unwrap(), "AGTC");
+        assert_eq!(super::number_to_pattern(0, 1).unwrap(), "A");
+        assert!(super::number_to_pattern(256, 4).is_err());
+        let pattern = "ACGTGCA".repeat(9) + "T";
+        let index = super::pattern_to_number(&pattern).unwrap();
+        assert_eq!(super::number_to_pattern(index, 64).unwrap(), pattern);
+    }
+
+    #[test]
+    fn computing_frequencies() {
+        let frequencies = super::computing_frequencies("ACGCGGCTCTGAAA", 2).unwrap();
+        assert_eq!(frequencies, vec![2, 1, 0, 0, 0, 0, 2, 2, 1, 2, 1, 0, 0, 1, 1, 0]);
+        assert!(super::computing_frequencies("AC", 3).is_err());
+        assert!(super::frequent_words_by_sorting("AC", 3).is_err());
+        assert!(super::computing_frequencies("ACGT", 13).is_err());
+    }
+
+    #[test]
+    fn frequent_words_by_sorting() {
+        let text = "ACGTTGCATGTCGCATGATGCATGAGAGCT";
+        assert_eq!(super::frequent_words_by_sorting(text, 4).unwrap(), vec!["CATG", "GCAT"]);
+        let mut frequent = super::count_kmers(text, 4).unwrap().find_frequent_kmers();
+        frequent.sort();
+        assert_eq!(super::frequent_words_by_sorting(text, 4).unwrap(), frequent);
+    }
+
+    #[test]
+    fn rolling_kmers() {
+        let kmers: Vec<_> = super::Kmers::new(b"ACGTA", 3).unwrap().collect();
+        assert_eq!(kmers, vec![(0, kmer("ACG")), (1, kmer("CGT")), (2, kmer("GTA"))]);
+        assert_eq!(super::Kmers::new(b"AC", 3).unwrap().count(), 0);
+        assert!(super::Kmers::new(b"ACGT", 0).is_err());
+    }
 
     #[test]
     fn count_kmers() {
         let dna = "ACAACTATGCATACTATCGGGAACTATCCT";
-        let kmer_counts = super::count_kmers(&dna, 5);
+        let kmer_counts = super::count_kmers(dna, 5).unwrap();
         let kmer_hash = kmer_counts.to_hashmap().clone();
-        let count = kmer_hash[&b"ACTAT"[..]];
+        let count = kmer_hash[&kmer("ACTAT")];
         assert_eq!(count, 3);
     }
 
     #[test]
-    fn combinations() {
-        let combs: Vec<_> = super::combinations(3, 2).collect();
-        assert_eq!(combs, vec![[0,1], [0,2], [1,2]]);
+    fn count_kmers_dna() {
+        use sequence::Dna;
+        let dna = Dna::new("ACAACTATGCATACTATCGGGAACTATCCT").unwrap();
+        let kmer_counts = super::count_kmers_dna(&dna, 5).unwrap();
+        assert_eq!(kmer_counts.to_hashmap()[&kmer("ACTAT")], 3);
+        assert!(super::count_kmers_dna(&dna, 31).is_err());
     }
 
     #[test]
-    fn product() {
-        let prod: Vec<_> = super::product(3,2).collect();
-        assert_eq!(prod, vec![[0,0],[0,1],[0,2],[1,0],[1,1],[1,2],[2,0],[2,1],[2,2]]);
+    fn count_kmers_threaded() {
+        let dna = "ACAACTATGCATACTATCGGGAACTATCCT";
+        let serial = super::count_kmers_threaded(dna, 3, 1).unwrap();
+        for threads in 0..8 {
+            let kmer_counts = super::count_kmers_threaded(dna, 3, threads).unwrap();
+            assert_eq!(kmer_counts.to_hashmap(), serial.to_hashmap());
+        }
+        let total: i32 = serial.to_hashmap().values().sum();
+        assert_eq!(total, 28);
     }
 
     #[test]
     fn neighborhood() {
-        let res = super::neighborhood("ATCGA", 2);
-        assert!(res.iter().all(|x| num_mismatches(b"ATCGA", x) <= 2));
-        assert!(res.contains(&b"ATCGA"[..]));
-        assert!(res.contains(&b"TTCGA"[..]));
-        assert!(res.contains(&b"ATTGA"[..]));
-        assert!(!res.contains(&b"AAAAA"[..]));
-        assert!(!res.contains(&b"ATCG"[..]));
-        assert!(super::neighborhood("GTTG", 1).contains(&b"GATG"[..]));
+        let res: HashSet<Kmer> = super::neighborhood(&kmer("ATCGA"), 2).collect();
+        assert!(res.iter().all(|x| num_mismatches(b"ATCGA", &x.to_bytes()) <= 2));
+        assert!(res.contains(&kmer("ATCGA")));
+        assert!(res.contains(&kmer("TTCGA")));
+        assert!(res.contains(&kmer("ATTGA")));
+        assert!(!res.contains(&kmer("AAAAA")));
+        assert!(super::neighborhood(&kmer("GTTG"), 1).any(|x| x == kmer("GATG")));
+        assert_eq!(super::neighborhood(&kmer("GT"), 0).collect::<Vec<_>>(), vec![kmer("GT")]);
+        assert_eq!(super::neighborhood(&kmer("GT"), 5).count(), 16);
+    }
+
+    #[test]
+    fn neighborhood_without_duplicates() {
+        // sum over distances j of C(12, j) * 3^j
+        let neighbors: Vec<Kmer> = super::neighborhood(&kmer("ACGTACGTACGT"), 3).collect();
+        assert_eq!(neighbors.len(), 1 + 36 + 594 + 5940);
+        let distinct: HashSet<&Kmer> = neighbors.iter().collect();
+        assert_eq!(distinct.len(), neighbors.len());
+
+        let mut immediate: Vec<String> = super::immediate_neighbors(&kmer("ACG")).iter()
+            .map(|x| x.to_string())
+            .collect();
+        immediate.sort();
+        assert_eq!(immediate, ["AAG", "ACA", "ACC", "ACG", "ACT", "AGG", "ATG", "CCG", "GCG", "TCG"]);
     }
 
     #[test]
     fn count_mismatch_kmers() {
         let text = "ACGTTGCATGTCGCATGATGCATGAGAGCT";
-        let counts = super::count_mismatch_kmers(text, 4, 1).to_hashmap().clone();
-        assert_eq!(counts[&b"GATG"[..]], 5);
+        let counts = super::count_mismatch_kmers(text, 4, 1).unwrap().to_hashmap().clone();
+        assert_eq!(counts[&kmer("GATG")], 5);
     }
 
     #[test]
     fn find_frequent_kmers() {
         let mut kmer_counts = super::KmerCounter::new();
-        kmer_counts.insert("AB", 4);
-        kmer_counts.insert("AC", 2);
-        kmer_counts.insert("AD", 4);
-        kmer_counts.insert("AE", 1);
+        kmer_counts.insert(kmer("AA"), 4);
+        kmer_counts.insert(kmer("AC"), 2);
+        kmer_counts.insert(kmer("AG"), 4);
+        kmer_counts.insert(kmer("AT"), 1);
         let mut frequent_kmers = kmer_counts.find_frequent_kmers();
         frequent_kmers.sort();
-        assert_eq!(frequent_kmers, ["AB", "AD"]);
+        assert_eq!(frequent_kmers, ["AA", "AG"]);
+    }
+
+    #[test]
+    fn counter_queries() {
+        let counts = super::count_kmers("ACGTTGCATGTCGCATGATGCATGAGAGCT", 4).unwrap();
+        let sorted = counts.sorted_counts();
+        assert_eq!(sorted.len(), 21);
+        assert_eq!(sorted[..3], [(kmer("CATG"), 3), (kmer("GCAT"), 3), (kmer("ATGA"), 2)]);
+        assert_eq!(counts.top_n(3), sorted[..3]);
+        assert_eq!(counts.top_n(100), sorted);
+        assert!(counts.top_n(0).is_empty());
+
+        let frequent = counts.with_min_count(2);
+        assert_eq!(frequent, sorted[..4]);
+        assert!(frequent.iter().all(|&(_, count)| count >= 2));
+
+        let histogram = counts.histogram();
+        let expected: Vec<(i32, usize)> = vec![(1, 17), (2, 2), (3, 2)];
+        assert_eq!(histogram.into_iter().collect::<Vec<_>>(), expected);
     }
 
     #[test]
     fn approximate_matches() {
         let pattern = "ATTCTGGA";
         let text = "CGCCCGAATCCAGAACGCATTCCCATATTTCGGGACCACTGGCCTCCACGGTACGGACGTCAATCAAATGCCTAGCGGCTTGTGGTTTCTCCTACGCTCC";
-        let result = super::find_matches(pattern, text, 3);
+        let result = super::find_matches(pattern, text, 3).unwrap();
         assert_eq!(result, vec![6, 7, 26, 27, 78]);
+        for &strategy in &[MatchStrategy::Neighborhood, MatchStrategy::Scan] {
+            assert_eq!(super::find_matches_with(pattern, text, 3, strategy).unwrap(), result);
+        }
+        assert_eq!(super::approximate_pattern_count("GAGG", "TTTAGAGCCTTCAGAGG", 2).unwrap(), 4);
+
+        // patterns longer than a packed kmer are compared directly
+        let pattern = "ACGT".repeat(17);
+        let text = format!("TT{}TT", pattern.replacen("ACGT", "AAGT", 1).to_lowercase());
+        assert_eq!(super::find_matches(&pattern, &text, 1).unwrap(), vec![2]);
+        assert!(super::find_matches(&pattern, &text, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn choose_strategy() {
+        assert_eq!(super::neighborhood_size(12, 3), 6571);
+        assert_eq!(super::neighborhood_size(2, 5), 16);
+        assert_eq!(super::choose_strategy(8, 1, 10000), MatchStrategy::Neighborhood);
+        assert_eq!(super::choose_strategy(20, 6, 10000), MatchStrategy::Scan);
+        assert_eq!(super::choose_strategy(65, 0, 10000), MatchStrategy::Scan);
     }
 
     #[test]
     fn kmer_composition() {
         let text = "CAATCCAAC";
-        let kmers = super::kmer_composition(text, 5);
+        let kmers = super::kmer_composition(text, 5).unwrap();
         assert_eq!(kmers, vec![b"AATCC",
                                b"ATCCA",
                                b"CAATC",
                                b"CCAAC",
                                b"TCCAA"]);
+        let text = "ACGT".repeat(20);
+        let kmers = super::kmer_composition(&text.to_lowercase(), 70).unwrap();
+        assert_eq!(kmers.len(), 11);
+        assert_eq!(kmers[0], &text.as_bytes()[..70]);
     }
 
     #[test]
     fn locate_kmers() {
         let genome = "CGACACGACATTGCGACATA";
-        let res = super::locate_kmers(genome, 5).to_hashmap().clone();
-        assert!(res.contains_key(&b"CGACA"[..]));
-        let locations = res.get(&b"CGACA"[..]).unwrap();
+        let res = super::locate_kmers(genome, 5).unwrap().to_hashmap().clone();
+        assert!(res.contains_key(&kmer("CGACA")));
+        let locations = res.get(&kmer("CGACA")).unwrap();
         assert_eq!(*locations, vec![0, 5, 13]);
     }
 
@@ -457,21 +1211,115 @@ mod test {
     #[test]
     fn find_clumps() {
         let mut kmer_locations = super::KmerLocator::new();
-        kmer_locations.insert_locations("A", vec![1,3,5,7]);
-        kmer_locations.insert_locations("B", vec![1,2,3,4]);
-        kmer_locations.insert_locations("C", vec![1,10,20,30]);
-
-        let res = kmer_locations.find_clumps(3, 3, 1);
-        assert_eq!(res, vec!["B".to_string()]);
+        kmer_locations.insert_locations("A", vec![1,3,5,7]).unwrap();
+        kmer_locations.insert_locations("C", vec![1,2,3,4]).unwrap();
+        kmer_locations.insert_locations("G", vec![1,10,20,30]).unwrap();
+        assert!(kmer_locations.insert_locations("B", vec![1]).is_err());
+
+        let params = super::ClumpParams { k: 1, l: 3, t: 3 };
+        let res = kmer_locations.find_clumps(&params).unwrap();
+        assert_eq!(res, vec!["C".to_string()]);
     }
 
     #[test]
     fn find_clumps2() {
         let genome = "CGGACTCGACAGATGTGAAGAAATGTGAAGACTGAGTGAAGAGAAGAGGAAACACGACACGACATTGCGACATAATGTACGAATGTAATGTGCCTATGGC";
-        let (k, l, t) = (5, 75, 4);
-        let kmer_locations = super::locate_kmers(genome, k);
-        let mut clumps = kmer_locations.find_clumps(l, t, k);
-        let mut expected = vec!["CGACA", "GAAGA", "AATGT"];
-        assert_eq!(clumps.sort(), expected.sort());
+        let params = super::ClumpParams { k: 5, l: 75, t: 4 };
+        let kmer_locations = super::locate_kmers(genome, params.k).unwrap();
+        let mut clumps = kmer_locations.find_clumps(&params).unwrap();
+        clumps.sort();
+        assert_eq!(clumps, ["AATGT", "CGACA", "GAAGA"]);
+
+        let mut streamed: Vec<String> = super::find_clumps(genome, &params).unwrap()
+            .iter()
+            .map(|x| x.kmer.to_string())
+            .collect();
+        streamed.sort();
+        assert_eq!(streamed, clumps);
+    }
+
+    #[test]
+    fn clumps_match_locator() {
+        // pseudo-random genome with repeats, so clumps open and close
+        // many times along it
+        let mut state = 7u32;
+        let genome: String = (0..3000).map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            b"AACGT"[(state >> 16) as usize % 5] as char
+        }).collect();
+        for &(k, l, t) in &[(3, 20, 3), (4, 50, 3), (2, 3, 2), (5, 200, 2)] {
+            let params = super::ClumpParams { k, l, t };
+            let mut expected = super::locate_kmers(&genome, k).unwrap().find_clumps(&params).unwrap();
+            expected.sort();
+            let mut streamed: Vec<String> = super::find_clumps(&genome, &params).unwrap()
+                .iter()
+                .map(|x| x.kmer.to_string())
+                .collect();
+            streamed.sort();
+            streamed.dedup();
+            assert_eq!(streamed, expected);
+        }
+    }
+
+    #[test]
+    fn clump_regions() {
+        // ACA at 0, 2, 4 and 14, 16, 18 forms two separate clumps
+        let genome = "ACACACATTTTTTTACACACA";
+        let params = super::ClumpParams { k: 3, l: 7, t: 3 };
+        let clumps = super::find_clumps(genome, &params).unwrap();
+        let regions: Vec<_> = clumps.iter().map(|x| (x.kmer.to_string(), x.start, x.end)).collect();
+        assert_eq!(regions, vec![("ACA".to_string(), 0, 7),
+                                 ("TTT".to_string(), 7, 14),
+                                 ("ACA".to_string(), 14, 21)]);
+
+        // a larger window merges overlapping clumps of the same kmer
+        let params = super::ClumpParams { k: 3, l: 9, t: 3 };
+        let clumps = super::find_clumps("ACACACACATTT", &params).unwrap();
+        assert_eq!((clumps[0].start, clumps[0].end), (0, 9));
+        assert_eq!(clumps.len(), 2);
+
+        let params = super::ClumpParams { k: 5, l: 4, t: 1 };
+        assert!(super::find_clumps(genome, &params).is_err());
+    }
+
+    #[test]
+    fn kmer_errors() {
+        use error::Error;
+        match super::count_kmers("ACGT", 5) {
+            Err(Error::KmerTooLong(5, 4)) => (),
+            x => panic!("unexpected result {:?}", x.map(|_| ())),
+        }
+        assert!(super::count_kmers("ACGT", 0).is_err());
+        assert!(super::locate_kmers("ACG", 4).is_err());
+        assert!(super::kmer_composition("ACG", 0).is_err());
+        assert!(super::kmer_composition("ACNT", 2).is_err());
+        assert!(super::find_matches("ACGTA", "ACGT", 1).is_err());
+        assert!(super::find_matches("ACG", "ACNTACG", 1).is_err());
+        let params = super::ClumpParams { k: 2, l: 1, t: 1 };
+        assert!(super::locate_kmers("ACGT", 2).unwrap().find_clumps(&params).is_err());
+    }
+
+    #[test]
+    fn unmasked_kmers() {
+        let starts = |text: &[u8], k| -> Vec<usize> {
+            super::Kmers::unmasked(text, k).unwrap().map(|(i, _)| i).collect()
+        };
+        assert_eq!(starts(b"ACGtaCGTA", 3), vec![0, 5, 6]);
+        assert_eq!(starts(b"acgt", 2), Vec::<usize>::new());
+        assert_eq!(starts(b"ACnnnACG", 3), vec![5]);
+        assert_eq!(starts(b"ACNNNACG", 3), vec![5]);
+
+        let counts = super::count_kmers_unmasked("ACGTacgtACGT", 4).unwrap();
+        let counts = counts.to_hashmap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[&kmer("ACGT")], 2);
+        let counts = super::count_kmers("ACGTacgtACGT", 4).unwrap();
+        assert_eq!(counts.to_hashmap()[&kmer("ACGT")], 3);
+
+        let res = super::locate_kmers_unmasked("ACGTacgtACGT", 3).unwrap();
+        let res = res.to_hashmap();
+        assert_eq!(res[&kmer("ACG")], vec![0, 8]);
+        assert_eq!(res[&kmer("CGT")], vec![1, 9]);
+        assert_eq!(res.len(), 2);
     }
 }
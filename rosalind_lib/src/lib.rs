@@ -2,3 +2,13 @@ pub mod kmers;
 pub mod dna_utils;
 pub mod io;
 pub mod matching;
+pub mod error;
+pub mod sequence;
+pub mod translation;
+pub mod orf;
+pub mod proteomics;
+pub mod cyclopeptide;
+pub mod combinatorics;
+pub mod parallel;
+
+pub use error::{Error, Result};
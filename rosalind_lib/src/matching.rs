@@ -1,23 +1,278 @@
+use std::collections::{BTreeSet, HashMap};
+use std::collections::hash_map::Entry;
+
+use dna_utils::{iupac_bases, within_distance};
+use error::{check_k, Error, Result};
+use kmers::{locate_kmers, Kmer, KmerLocator};
+
 /// do pattern matching
 ///
-pub fn naive(pattern: &str, genome: &str) -> Vec<usize> {
+pub fn naive(pattern: &str, genome: &str) -> Result<Vec<usize>> {
+    let pattern = pattern.as_bytes();
+    let genome = genome.as_bytes();
     let k = pattern.len();
     let n = genome.len();
+    check_k(k, n)?;
     let mut res = Vec::new();
 
-    for i in 0..n-k {
+    for i in 0..n-k+1 {
         if pattern == &genome[i..i+k] { res.push(i); }
     }
+    Ok(res)
+}
+
+/// prefix function (KMP failure array), the length of the longest
+/// proper prefix of s[..i+1] that is also a suffix of it
+///
+pub fn prefix_function(s: &[u8]) -> Vec<usize> {
+    let mut res = vec![0; s.len()];
+    for i in 1..s.len() {
+        // fall back through shorter borders until one extends
+        let mut j = res[i - 1];
+        while j > 0 && s[i] != s[j] { j = res[j - 1]; }
+        if s[i] == s[j] { j += 1; }
+        res[i] = j;
+    }
     res
 }
 
+/// Knuth-Morris-Pratt pattern matching in linear time
+///
+pub fn kmp(pattern: &str, genome: &str) -> Result<Vec<usize>> {
+    let pattern = pattern.as_bytes();
+    let genome = genome.as_bytes();
+    let k = pattern.len();
+    check_k(k, genome.len())?;
+    let failure = prefix_function(pattern);
+    let mut res = Vec::new();
+
+    // length of the pattern prefix matched so far
+    let mut j = 0;
+    for (i, &c) in genome.iter().enumerate() {
+        while j > 0 && c != pattern[j] { j = failure[j - 1]; }
+        if c == pattern[j] { j += 1; }
+        if j == k {
+            res.push(i + 1 - k);
+            j = failure[j - 1];
+        }
+    }
+    Ok(res)
+}
+
+/// Z-array, the length of the longest substring starting at each
+/// position that is also a prefix of s; the first entry is the
+/// length of s
+///
+pub fn z_array(s: &[u8]) -> Vec<usize> {
+    let n = s.len();
+    let mut z = vec![0; n];
+    if n == 0 { return z; }
+    z[0] = n;
+
+    // [left, right) is the rightmost window known to match a prefix
+    let (mut left, mut right) = (0, 0);
+    for i in 1..n {
+        if i < right { z[i] = z[i - left].min(right - i); }
+        while i + z[i] < n && s[z[i]] == s[i + z[i]] { z[i] += 1; }
+        if i + z[i] > right {
+            left = i;
+            right = i + z[i];
+        }
+    }
+    z
+}
+
+/// pattern matching with the Z-algorithm in linear time
+///
+pub fn z_algorithm(pattern: &str, genome: &str) -> Result<Vec<usize>> {
+    let k = pattern.len();
+    let n = genome.len();
+    check_k(k, n)?;
+
+    // a position of the genome matches when its Z value in the
+    // concatenation reaches the pattern length
+    let mut text = Vec::with_capacity(k + n);
+    text.extend_from_slice(pattern.as_bytes());
+    text.extend_from_slice(genome.as_bytes());
+    let z = z_array(&text);
+
+    Ok((0..n-k+1).filter(|&i| z[k + i] >= k).collect())
+}
+
+/// convert a sequence to IUPAC base sets
+///
+fn to_iupac_bases(seq: &[u8]) -> Result<Vec<u8>> {
+    seq.iter().enumerate().map(|(i, &c)| {
+        iupac_bases(c).ok_or(Error::InvalidCharacter(c as char, i))
+    }).collect()
+}
+
+/// pattern matching with IUPAC ambiguity codes, a genome position
+/// matches if every base it stands for is allowed by the pattern
+///
+pub fn iupac(pattern: &str, genome: &str) -> Result<Vec<usize>> {
+    let pattern = to_iupac_bases(pattern.as_bytes())?;
+    let genome = to_iupac_bases(genome.as_bytes())?;
+    let k = pattern.len();
+    let n = genome.len();
+    check_k(k, n)?;
+    let mut res = Vec::new();
+
+    for i in 0..n-k+1 {
+        let window = &genome[i..i+k];
+        if window.iter().zip(&pattern).all(|(&g, &p)| g & p == g) {
+            res.push(i);
+        }
+    }
+    Ok(res)
+}
+
+/// check that a sequence only has ACGT bases, in either case,
+/// returning it in uppercase
+///
+fn uppercase_dna(seq: &str) -> Result<String> {
+    match seq.char_indices().find(|&(_, c)| !"ACGTacgt".contains(c)) {
+        Some((i, c)) => Err(Error::InvalidCharacter(c, i)),
+        None => Ok(seq.to_ascii_uppercase()),
+    }
+}
+
+/// multiple approximate pattern matching by seed and extend, returns
+/// the start of every match with at most d mismatches for each
+/// pattern
+///
+/// by the pigeonhole principle, a pattern split into d+1 disjoint
+/// seeds has at least one seed matching exactly at any approximate
+/// match, so only positions where some seed matches exactly are
+/// verified
+///
+pub fn multiple_approximate_matching(patterns: &[&str], genome: &str, d: usize) -> Result<Vec<Vec<usize>>> {
+    let genome = uppercase_dna(genome)?;
+    let n = genome.len();
+    // kmer locators of the genome for each seed length used
+    let mut locators: HashMap<usize, KmerLocator> = HashMap::new();
+    let mut res = Vec::with_capacity(patterns.len());
+
+    for pattern in patterns {
+        let pattern = uppercase_dna(pattern)?;
+        let k = pattern.len();
+        // a pattern longer than the genome has no matches, the rest
+        // of the batch is still matched
+        if k > n {
+            res.push(Vec::new());
+            continue;
+        }
+        check_k(k, n)?;
+        let seed_length = (k / (d + 1)).min(Kmer::MAX_K);
+
+        // patterns too short to seed are checked at every position
+        let candidates: BTreeSet<usize> = if seed_length == 0 {
+            (0..n-k+1).collect()
+        } else {
+            let locator = match locators.entry(seed_length) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(locate_kmers(&genome, seed_length)?),
+            };
+            let locations = locator.to_hashmap();
+
+            let mut candidates = BTreeSet::new();
+            for offset in (0..d+1).map(|i| i * seed_length) {
+                let seed: Kmer = pattern[offset..offset+seed_length].parse()?;
+                let hits = locations.get(&seed).map_or(&[][..], |x| &x[..]);
+                candidates.extend(hits.iter()
+                    .filter(|&&hit| hit >= offset && hit - offset + k <= n)
+                    .map(|&hit| hit - offset));
+            }
+            candidates
+        };
+
+        let (pattern, genome) = (pattern.as_bytes(), genome.as_bytes());
+        res.push(candidates.into_iter()
+            .filter(|&start| within_distance(pattern, &genome[start..start+k], d))
+            .collect());
+    }
+    Ok(res)
+}
+
 #[cfg(test)]
 mod test {
     #[test]
     fn naive() {
         let pattern = "ATAT";
         let genome = "GATATATGCATATACTT";
-        let res = super::naive(pattern, genome);
+        let res = super::naive(pattern, genome).unwrap();
         assert_eq!(res, vec![1, 3, 9]);
     }
+
+    #[test]
+    fn naive_errors() {
+        assert!(super::naive("", "ACGT").is_err());
+        assert!(super::naive("ACGTA", "ACGT").is_err());
+    }
+
+    #[test]
+    fn match_at_last_position() {
+        assert_eq!(super::naive("CTT", "GATCTT").unwrap(), vec![3]);
+        assert_eq!(super::naive("ACGT", "ACGT").unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn prefix_function() {
+        let res = super::prefix_function(b"CAGCATGGTATCACAGCAGAG");
+        assert_eq!(res, vec![0, 0, 0, 1, 2, 0, 0, 0, 0, 0, 0, 1, 2, 1, 2, 3, 4, 5, 3, 0, 0]);
+        assert!(super::prefix_function(b"").is_empty());
+    }
+
+    #[test]
+    fn z_array() {
+        assert_eq!(super::z_array(b"AABXAAB"), vec![7, 1, 0, 0, 3, 1, 0]);
+        assert_eq!(super::z_array(b"AAAA"), vec![4, 3, 2, 1]);
+        assert!(super::z_array(b"").is_empty());
+    }
+
+    #[test]
+    fn exact_matchers_agree() {
+        let genome = "GATATATGCATATACTTATAT";
+        for pattern in &["ATAT", "A", "GATATATGCATATACTTATAT", "CCC", "TAT"] {
+            let expected = super::naive(pattern, genome).unwrap();
+            assert_eq!(super::kmp(pattern, genome).unwrap(), expected);
+            assert_eq!(super::z_algorithm(pattern, genome).unwrap(), expected);
+        }
+        assert_eq!(super::kmp("ATAT", genome).unwrap(), vec![1, 3, 9, 17]);
+        assert!(super::kmp("", genome).is_err());
+        assert!(super::z_algorithm("ACGTA", "ACGT").is_err());
+    }
+
+    #[test]
+    fn iupac() {
+        let genome = "CCGAATTCAAAGGAATTCTTTAGAATTCGGGCC";
+        let res = super::iupac("GAATTCNNNR", genome).unwrap();
+        assert_eq!(res, vec![2, 12]);
+        assert_eq!(super::iupac("GAATTC", "GAATTCNAATTC").unwrap(), vec![0]);
+        assert_eq!(super::iupac("NAATTC", "GAATTCNAATTC").unwrap(), vec![0, 6]);
+        assert!(super::iupac("GAAXTC", genome).is_err());
+    }
+
+    #[test]
+    fn multiple_approximate_matching() {
+        let res = super::multiple_approximate_matching(&["ATT", "GCC", "GCTA", "TATT"], "ACATGCTACTTT", 1).unwrap();
+        assert_eq!(res, vec![vec![2, 7, 8, 9], vec![4], vec![4], vec![6]]);
+
+        // agrees with scanning the genome for each pattern
+        let genome = "CGCCCGAATCCAGAACGCATTCCCATATTTCGGGACCACTGGCCTCCACGGTACGGACGTCAATCAAATGCCTAGCGGCTTGTGGTTTCTCCTACGCTCC";
+        let patterns = ["ATTCTGGA", "cgcattccc", "GG", "AATCAAATGCCTAGCGG"];
+        for d in 0..4 {
+            let res = super::multiple_approximate_matching(&patterns, genome, d).unwrap();
+            for (pattern, positions) in patterns.iter().zip(res) {
+                assert_eq!(positions, ::kmers::find_matches(pattern, genome, d).unwrap());
+            }
+        }
+
+        assert!(super::multiple_approximate_matching(&["ACN"], genome, 1).is_err());
+        assert!(super::multiple_approximate_matching(&[""], genome, 1).is_err());
+        assert!(super::multiple_approximate_matching(&[], genome, 1).unwrap().is_empty());
+
+        let res = super::multiple_approximate_matching(&["ACATGCTACTTTA", "GCC"], "ACATGCTACTTT", 1).unwrap();
+        assert_eq!(res, vec![vec![], vec![4]]);
+    }
 }
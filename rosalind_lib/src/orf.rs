@@ -0,0 +1,169 @@
+use dna_utils::revcomp;
+use error::Result;
+use translation::GeneticCode;
+pub use translation::Strand;
+
+/// an open reading frame, start and end are 0-based, half-open
+/// coordinates on the forward strand and include the stop codon,
+/// frame is the offset (0, 1 or 2) of the ORF on its own strand
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orf {
+    pub start: usize,
+    pub end: usize,
+    pub frame: usize,
+    pub strand: Strand,
+    pub protein: String,
+}
+
+/// options for ORF discovery
+///
+/// `min_length` is the shortest protein reported, in amino acids,
+/// `alternative_starts` allows every start codon of the genetic
+/// code instead of ATG only and `nested` reports ORFs starting at
+/// every in-frame start codon instead of only the longest one
+///
+#[derive(Debug, Clone)]
+pub struct OrfOptions {
+    pub code: &'static GeneticCode,
+    pub min_length: usize,
+    pub alternative_starts: bool,
+    pub nested: bool,
+}
+
+impl Default for OrfOptions {
+    fn default() -> Self {
+        OrfOptions {
+            code: GeneticCode::standard(),
+            min_length: 1,
+            alternative_starts: false,
+            nested: false,
+        }
+    }
+}
+
+impl OrfOptions {
+    fn is_start(&self, codon: &[u8]) -> bool {
+        if self.alternative_starts {
+            self.code.is_start(codon)
+        } else {
+            codon.eq_ignore_ascii_case(b"ATG")
+        }
+    }
+}
+
+/// find ORFs in the three frames of one strand, codons with
+/// ambiguous bases are neither start nor stop codons and are
+/// translated as 'X'
+///
+fn find_strand_orfs(seq: &str, strand: Strand, options: &OrfOptions) -> Vec<Orf> {
+    let bytes = seq.as_bytes();
+    let n = bytes.len();
+    let mut orfs = Vec::new();
+
+    for frame in 0..3 {
+        // in-frame start codons not yet closed by a stop codon
+        let mut starts: Vec<usize> = Vec::new();
+
+        let mut position = frame;
+        while position + 3 <= n {
+            let codon = &bytes[position..position+3];
+            if options.code.is_stop(codon) {
+                let end = position + 3;
+                let reported = if options.nested { starts.len() } else { starts.len().min(1) };
+                for &start in &starts[..reported] {
+                    // alternative start codons are still read as methionine
+                    let protein: String = bytes[start+3..position].chunks(3)
+                        .map(|codon| options.code.translate_codon(codon).unwrap_or(b'X') as char)
+                        .collect();
+                    let protein = format!("M{}", protein);
+                    if protein.len() < options.min_length { continue; }
+
+                    let (start, end) = match strand {
+                        Strand::Forward => (start, end),
+                        Strand::Reverse => (n - end, n - start),
+                    };
+                    orfs.push(Orf { start, end, frame, strand, protein });
+                }
+                starts.clear();
+            } else if options.is_start(codon) {
+                starts.push(position);
+            }
+            position += 3;
+        }
+    }
+    orfs
+}
+
+/// find ORFs in all six reading frames of a DNA sequence, sorted
+/// by position, IUPAC ambiguity codes such as N are allowed
+///
+pub fn find_orfs(dna: &str, options: &OrfOptions) -> Result<Vec<Orf>> {
+    let reverse = revcomp(dna)?;
+    let mut orfs = find_strand_orfs(dna, Strand::Forward, options);
+    orfs.extend(find_strand_orfs(&reverse, Strand::Reverse, options));
+
+    orfs.sort_by_key(|x| (x.start, x.end, x.strand));
+    Ok(orfs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OrfOptions, Strand};
+
+    #[test]
+    fn find_orfs() {
+        let dna = "AGCCATGTAGCTAACTCAGGTTACATGGGGATGACCCCGCGACTTGGATTAGAGTCTCTTTTGGAATAAGCCTGAATGATCCGAGTAGCATCTCAG";
+        let options = OrfOptions { nested: true, ..Default::default() };
+        let orfs = super::find_orfs(dna, &options).unwrap();
+        let mut proteins: Vec<_> = orfs.iter().map(|x| x.protein.as_str()).collect();
+        proteins.sort();
+        proteins.dedup();
+        assert_eq!(proteins, vec!["M", "MGMTPRLGLESLLE", "MLLGSFRLIPKETLIQVAGSSPCNLS", "MTPRLGLESLLE"]);
+
+        let orf = orfs.iter().find(|x| x.protein == "MGMTPRLGLESLLE").unwrap();
+        assert_eq!((orf.start, orf.end, orf.frame, orf.strand), (24, 69, 0, Strand::Forward));
+        assert_eq!(&dna[orf.start..orf.start+3], "ATG");
+
+        let orf = orfs.iter().find(|x| x.protein == "MLLGSFRLIPKETLIQVAGSSPCNLS").unwrap();
+        assert_eq!(orf.strand, Strand::Reverse);
+        assert_eq!(&dna[orf.end-3..orf.end], "CAT");
+
+        // without nesting only the longest ORF per stop codon is kept
+        let orfs = super::find_orfs(dna, &OrfOptions::default()).unwrap();
+        assert!(orfs.iter().all(|x| x.protein != "MTPRLGLESLLE"));
+
+        let options = OrfOptions { min_length: 5, ..Default::default() };
+        let orfs = super::find_orfs(dna, &options).unwrap();
+        assert_eq!(orfs.len(), 2);
+    }
+
+    #[test]
+    fn alternative_starts() {
+        // TTG is a start codon in the bacterial code
+        let dna = "CCTTGAAATAG";
+        assert!(super::find_orfs(dna, &OrfOptions::default()).unwrap().is_empty());
+
+        let options = OrfOptions {
+            code: ::translation::GeneticCode::from_id(11).unwrap(),
+            alternative_starts: true,
+            ..Default::default()
+        };
+        let orfs = super::find_orfs(dna, &options).unwrap();
+        assert_eq!(orfs.len(), 1);
+        assert_eq!(orfs[0].protein, "MK");
+        assert_eq!((orfs[0].start, orfs[0].end, orfs[0].frame), (2, 11, 2));
+    }
+
+    #[test]
+    fn ambiguous_bases() {
+        let options = OrfOptions::default();
+        let orfs = super::find_orfs("ATGCCNTAGNNNATGTAA", &options).unwrap();
+        let proteins: Vec<_> = orfs.iter().map(|x| x.protein.as_str()).collect();
+        assert_eq!(proteins, vec!["MX", "M"]);
+
+        // a stop codon with an N in it does not end the ORF
+        assert!(super::find_orfs("ATGCCCTNG", &options).unwrap().is_empty());
+        assert!(super::find_orfs("ATGXTAG", &options).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use std::io;
+use std::ops::Range;
+use std::thread;
+
+use error::{Error, Result};
+
+/// default number of threads for parallel work
+///
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |x| x.get())
+}
+
+/// split the indices 0..n into one contiguous range per thread and
+/// call `f` on each range in its own scoped thread, results are
+/// returned in range order; small inputs and a single thread run
+/// on the calling thread
+///
+pub(crate) fn map_ranges<T, F>(n: usize, threads: usize, f: F) -> Result<Vec<T>>
+    where F: Fn(Range<usize>) -> T + Sync,
+          T: Send
+{
+    let threads = threads.max(1);
+    if threads == 1 || n < 2 * threads {
+        return Ok(vec![f(0..n)]);
+    }
+
+    let chunk_size = n.div_ceil(threads);
+    let f = &f;
+    thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for first in (0..n).step_by(chunk_size) {
+            let last = (first + chunk_size).min(n);
+            let handle = thread::Builder::new()
+                .spawn_scoped(scope, move || f(first..last))?;
+            handles.push(handle);
+        }
+
+        handles.into_iter()
+            .map(|h| h.join().map_err(|_| Error::Io(io::Error::other("worker thread panicked"))))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn map_ranges() {
+        for threads in 0..6 {
+            let ranges = super::map_ranges(10, threads, |x| x).unwrap();
+            let covered: Vec<usize> = ranges.into_iter().flatten().collect();
+            assert_eq!(covered, (0..10).collect::<Vec<_>>());
+        }
+        assert_eq!(super::map_ranges(0, 4, |x| x.len()).unwrap(), vec![0]);
+    }
+}
@@ -0,0 +1,181 @@
+use error::{Error, Result};
+
+/// monoisotopic residue mass of an amino acid, in daltons
+///
+pub fn monoisotopic_mass(amino_acid: u8) -> Option<f64> {
+    match amino_acid {
+        b'A' => Some(71.03711),
+        b'C' => Some(103.00919),
+        b'D' => Some(115.02694),
+        b'E' => Some(129.04259),
+        b'F' => Some(147.06841),
+        b'G' => Some(57.02146),
+        b'H' => Some(137.05891),
+        b'I' => Some(113.08406),
+        b'K' => Some(128.09496),
+        b'L' => Some(113.08406),
+        b'M' => Some(131.04049),
+        b'N' => Some(114.04293),
+        b'P' => Some(97.05276),
+        b'Q' => Some(128.05858),
+        b'R' => Some(156.10111),
+        b'S' => Some(87.03203),
+        b'T' => Some(101.04768),
+        b'V' => Some(99.06841),
+        b'W' => Some(186.07931),
+        b'Y' => Some(163.06333),
+        _ => None,
+    }
+}
+
+/// integer residue mass of an amino acid
+///
+pub fn integer_mass(amino_acid: u8) -> Option<u32> {
+    match amino_acid {
+        b'G' => Some(57),
+        b'A' => Some(71),
+        b'S' => Some(87),
+        b'P' => Some(97),
+        b'V' => Some(99),
+        b'T' => Some(101),
+        b'C' => Some(103),
+        b'I' | b'L' => Some(113),
+        b'N' => Some(114),
+        b'D' => Some(115),
+        b'K' | b'Q' => Some(128),
+        b'E' => Some(129),
+        b'M' => Some(131),
+        b'H' => Some(137),
+        b'F' => Some(147),
+        b'R' => Some(156),
+        b'Y' => Some(163),
+        b'W' => Some(186),
+        _ => None,
+    }
+}
+
+/// the distinct integer amino acid masses, in increasing order
+///
+pub const INTEGER_MASSES: [u32; 18] = [57, 71, 87, 97, 99, 101, 103, 113, 114,
+                                       115, 128, 129, 131, 137, 147, 156, 163, 186];
+
+/// integer masses of each amino acid in a peptide
+///
+pub fn peptide_masses(peptide: &str) -> Result<Vec<u32>> {
+    peptide.bytes().enumerate().map(|(i, c)| {
+        integer_mass(c).ok_or(Error::InvalidCharacter(c as char, i))
+    }).collect()
+}
+
+/// total monoisotopic mass of a peptide's residues
+///
+pub fn peptide_mass(peptide: &str) -> Result<f64> {
+    peptide.bytes().enumerate().map(|(i, c)| {
+        monoisotopic_mass(c).ok_or(Error::InvalidCharacter(c as char, i))
+    }).sum()
+}
+
+/// total integer mass of a peptide
+///
+pub fn peptide_integer_mass(peptide: &str) -> Result<u32> {
+    Ok(peptide_masses(peptide)?.iter().sum())
+}
+
+/// prefix sums of a list of masses, starting at 0
+///
+fn prefix_masses(masses: &[u32]) -> Vec<u32> {
+    let mut prefix = Vec::with_capacity(masses.len() + 1);
+    prefix.push(0);
+    for (i, mass) in masses.iter().enumerate() {
+        let total = prefix[i] + mass;
+        prefix.push(total);
+    }
+    prefix
+}
+
+/// sorted masses of every contiguous subpeptide of a linear
+/// peptide, including the empty peptide and the whole peptide
+///
+pub fn linear_spectrum_masses(masses: &[u32]) -> Vec<u32> {
+    let n = masses.len();
+    let prefix = prefix_masses(masses);
+    let mut spectrum = vec![0];
+
+    for i in 0..n {
+        for j in i+1..n+1 {
+            spectrum.push(prefix[j] - prefix[i]);
+        }
+    }
+    spectrum.sort();
+    spectrum
+}
+
+/// sorted masses of every contiguous subpeptide of a cyclic
+/// peptide, including subpeptides that wrap around the end
+///
+pub fn cyclic_spectrum_masses(masses: &[u32]) -> Vec<u32> {
+    let n = masses.len();
+    let prefix = prefix_masses(masses);
+    let total = prefix[n];
+    let mut spectrum = vec![0];
+
+    for i in 0..n {
+        for j in i+1..n+1 {
+            let mass = prefix[j] - prefix[i];
+            spectrum.push(mass);
+            // the complement of this subpeptide wraps around the cycle
+            if i > 0 && j < n {
+                spectrum.push(total - mass);
+            }
+        }
+    }
+    spectrum.sort();
+    spectrum
+}
+
+/// theoretical spectrum of a linear peptide
+///
+pub fn linear_spectrum(peptide: &str) -> Result<Vec<u32>> {
+    Ok(linear_spectrum_masses(&peptide_masses(peptide)?))
+}
+
+/// theoretical spectrum of a cyclic peptide
+///
+pub fn cyclic_spectrum(peptide: &str) -> Result<Vec<u32>> {
+    Ok(cyclic_spectrum_masses(&peptide_masses(peptide)?))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn peptide_mass() {
+        let mass = super::peptide_mass("SKADYEK").unwrap();
+        assert!((mass - 821.392).abs() < 0.001);
+        assert_eq!(super::peptide_integer_mass("NQEL").unwrap(), 484);
+        assert!(super::peptide_mass("SKADYEKX").is_err());
+    }
+
+    #[test]
+    fn integer_masses() {
+        let mut masses: Vec<_> = b"ACDEFGHIKLMNPQRSTVWY".iter()
+            .map(|&c| super::integer_mass(c).unwrap())
+            .collect();
+        masses.sort();
+        masses.dedup();
+        assert_eq!(masses, super::INTEGER_MASSES);
+    }
+
+    #[test]
+    fn linear_spectrum() {
+        let spectrum = super::linear_spectrum("NQEL").unwrap();
+        assert_eq!(spectrum, vec![0, 113, 114, 128, 129, 242, 242, 257, 370, 371, 484]);
+    }
+
+    #[test]
+    fn cyclic_spectrum() {
+        let spectrum = super::cyclic_spectrum("LEQN").unwrap();
+        assert_eq!(spectrum, vec![0, 113, 114, 128, 129, 227, 242, 242, 257, 355, 356, 370, 371, 484]);
+        assert_eq!(super::cyclic_spectrum("").unwrap(), vec![0]);
+        assert!(super::cyclic_spectrum("LEQB").is_err());
+    }
+}
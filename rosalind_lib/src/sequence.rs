@@ -0,0 +1,213 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use error::{Error, Result};
+
+/// the set of symbols allowed in a sequence
+///
+pub trait Alphabet {
+    const SYMBOLS: &'static [u8];
+
+    fn is_valid(c: u8) -> bool {
+        Self::SYMBOLS.contains(&c)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DnaAlphabet;
+
+impl Alphabet for DnaAlphabet {
+    const SYMBOLS: &'static [u8] = b"ACGT";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RnaAlphabet;
+
+impl Alphabet for RnaAlphabet {
+    const SYMBOLS: &'static [u8] = b"ACGU";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProteinAlphabet;
+
+impl Alphabet for ProteinAlphabet {
+    const SYMBOLS: &'static [u8] = b"ACDEFGHIKLMNPQRSTVWY";
+}
+
+/// a sequence checked against its alphabet on construction
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sequence<A: Alphabet> {
+    seq: String,
+    alphabet: PhantomData<A>,
+}
+
+pub type Dna = Sequence<DnaAlphabet>;
+pub type Rna = Sequence<RnaAlphabet>;
+pub type Protein = Sequence<ProteinAlphabet>;
+
+impl<A: Alphabet> Sequence<A> {
+    /// validate a sequence, symbols must match the alphabet exactly
+    ///
+    pub fn new(seq: &str) -> Result<Self> {
+        if let Some((i, c)) = seq.char_indices().find(|&(_, c)| !c.is_ascii() || !A::is_valid(c as u8)) {
+            return Err(Error::InvalidCharacter(c, i));
+        }
+        Ok(Sequence::from_valid(seq.to_string()))
+    }
+
+    /// validate a sequence ignoring case, the sequence is stored
+    /// in uppercase
+    ///
+    pub fn new_ignore_case(seq: &str) -> Result<Self> {
+        Sequence::new(&seq.to_ascii_uppercase())
+    }
+
+    fn from_valid(seq: String) -> Self {
+        Sequence { seq, alphabet: PhantomData }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.seq
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.seq.as_bytes()
+    }
+
+    pub fn into_string(self) -> String {
+        self.seq
+    }
+
+    pub fn len(&self) -> usize {
+        self.seq.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seq.is_empty()
+    }
+
+    /// count occurrences of each symbol, in alphabet order
+    ///
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; A::SYMBOLS.len()];
+        for c in self.seq.bytes() {
+            if let Some(i) = A::SYMBOLS.iter().position(|&x| x == c) {
+                counts[i] += 1;
+            }
+        }
+        counts
+    }
+}
+
+impl<A: Alphabet> FromStr for Sequence<A> {
+    type Err = Error;
+
+    fn from_str(seq: &str) -> Result<Self> {
+        Sequence::new(seq)
+    }
+}
+
+impl<A: Alphabet> fmt::Display for Sequence<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.seq)
+    }
+}
+
+impl<A: Alphabet> AsRef<str> for Sequence<A> {
+    fn as_ref(&self) -> &str {
+        &self.seq
+    }
+}
+
+/// complement of a base in the DNA alphabet
+///
+fn complement_dna_base(c: char) -> char {
+    match c {
+        'A' => 'T',
+        'C' => 'G',
+        'G' => 'C',
+        'T' => 'A',
+         _  => unreachable!("'{}' is not in the DNA alphabet", c),
+    }
+}
+
+impl Sequence<DnaAlphabet> {
+    pub fn complement(&self) -> Dna {
+        let seq = self.seq.chars().map(complement_dna_base).collect();
+        Sequence::from_valid(seq)
+    }
+
+    pub fn revcomp(&self) -> Dna {
+        let seq = self.seq.chars().rev().map(complement_dna_base).collect();
+        Sequence::from_valid(seq)
+    }
+
+    pub fn transcribe(&self) -> Rna {
+        Sequence::from_valid(self.seq.replace('T', "U"))
+    }
+
+    /// percentage of G and C bases, fails on an empty sequence
+    ///
+    pub fn gc_content(&self) -> Result<f32> {
+        if self.is_empty() { return Err(Error::EmptySequence); }
+        let count = self.seq.bytes().filter(|&c| c == b'C' || c == b'G').count();
+        Ok(100.0 * count as f32 / self.len() as f32)
+    }
+}
+
+impl Sequence<RnaAlphabet> {
+    pub fn back_transcribe(&self) -> Dna {
+        Sequence::from_valid(self.seq.replace('U', "T"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Dna, Protein, Rna};
+    use error::Error;
+
+    #[test]
+    fn validation() {
+        assert!(Dna::new("ACGT").is_ok());
+        match Dna::new("ACGU") {
+            Err(Error::InvalidCharacter('U', 3)) => (),
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert!(Dna::new("acgt").is_err());
+        assert_eq!(Dna::new_ignore_case("acGt").unwrap().as_str(), "ACGT");
+        assert!(Rna::new("ACGU").is_ok());
+        assert!(Rna::new("ACGT").is_err());
+        assert!(Protein::new("MAMAPRTEINSTRING").is_ok());
+        assert!(Protein::new("MAMAPRTEINSTRINGX").is_err());
+        assert!("ACGT".parse::<Dna>().is_ok());
+        assert!(Dna::new("ACGTé").is_err());
+    }
+
+    #[test]
+    fn counts() {
+        let dna = Dna::new("AGCTTTTCATTCTGACTGCAACGGGCAATATGTCTCTGTGTGGATTAAAAAAAGAGTGTCTGATAGCAGC").unwrap();
+        assert_eq!(dna.counts(), vec![20, 12, 17, 21]);
+        assert_eq!(Dna::new("").unwrap().counts(), vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn dna_operations() {
+        let dna = Dna::new("AAAACCCGGT").unwrap();
+        assert_eq!(dna.complement().as_str(), "TTTTGGGCCA");
+        assert_eq!(dna.revcomp().as_str(), "ACCGGGTTTT");
+        assert_eq!(dna.transcribe().as_str(), "AAAACCCGGU");
+        assert_eq!(dna.transcribe().back_transcribe(), dna);
+        assert_eq!(dna.gc_content().unwrap(), 50.0);
+        assert!(Dna::new("").unwrap().gc_content().is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        let mut seqs = [Dna::new("GT").unwrap(), Dna::new("ACG").unwrap(), Dna::new("AC").unwrap()];
+        seqs.sort();
+        let seqs: Vec<&str> = seqs.iter().map(|x| x.as_str()).collect();
+        assert_eq!(seqs, vec!["AC", "ACG", "GT"]);
+    }
+}
@@ -0,0 +1,340 @@
+use dna_utils::revcomp;
+use error::{check_k, Error, Result};
+
+/// an NCBI genetic code, amino acids and start codons are listed
+/// for codons in TCAG order (TTT, TTC, TTA, TTG, TCT, ...)
+///
+#[derive(Debug, PartialEq)]
+pub struct GeneticCode {
+    id: u8,
+    name: &'static str,
+    amino_acids: &'static [u8; 64],
+    starts: &'static [u8; 64],
+}
+
+static GENETIC_CODES: [GeneticCode; 20] = [
+    GeneticCode {
+        id: 1,
+        name: "Standard",
+        amino_acids: b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"---M------**--*----M---------------M----------------------------",
+    },
+    GeneticCode {
+        id: 2,
+        name: "Vertebrate Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        starts:      b"----------**--------------------MMMM----------**---M------------",
+    },
+    GeneticCode {
+        id: 3,
+        name: "Yeast Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"----------**----------------------MM---------------M------------",
+    },
+    GeneticCode {
+        id: 4,
+        name: "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"--MM------**-------M------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 5,
+        name: "Invertebrate Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        starts:      b"---M------**--------------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 6,
+        name: "Ciliate, Dasycladacean and Hexamita Nuclear",
+        amino_acids: b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"--------------*--------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 9,
+        name: "Echinoderm and Flatworm Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts:      b"----------**-----------------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 10,
+        name: "Euplotid Nuclear",
+        amino_acids: b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"----------**-----------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 11,
+        name: "Bacterial, Archaeal and Plant Plastid",
+        amino_acids: b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"---M------**--*----M------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 12,
+        name: "Alternative Yeast Nuclear",
+        amino_acids: b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"----------**--*----M---------------M----------------------------",
+    },
+    GeneticCode {
+        id: 13,
+        name: "Ascidian Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        starts:      b"---M------**----------------------MM---------------M------------",
+    },
+    GeneticCode {
+        id: 14,
+        name: "Alternative Flatworm Mitochondrial",
+        amino_acids: b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts:      b"-----------*-----------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 16,
+        name: "Chlorophycean Mitochondrial",
+        amino_acids: b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"----------*---*--------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 21,
+        name: "Trematode Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts:      b"----------**-----------------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 22,
+        name: "Scenedesmus obliquus Mitochondrial",
+        amino_acids: b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"------*---*---*--------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 23,
+        name: "Thraustochytrium Mitochondrial",
+        amino_acids: b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"--*-------**--*-----------------M--M---------------M------------",
+    },
+    GeneticCode {
+        id: 24,
+        name: "Rhabdopleuridae Mitochondrial",
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        starts:      b"---M------**-------M---------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 25,
+        name: "Candidate Division SR1 and Gracilibacteria",
+        amino_acids: b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"---M------**-----------------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 26,
+        name: "Pachysolen tannophilus Nuclear",
+        amino_acids: b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts:      b"----------**--*----M---------------M----------------------------",
+    },
+    GeneticCode {
+        id: 33,
+        name: "Cephalodiscidae Mitochondrial",
+        amino_acids: b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        starts:      b"---M-------*-------M---------------M---------------M------------",
+    },
+];
+
+/// position of a nucleotide in TCAG order, DNA or RNA, any case
+///
+fn base_index(c: u8) -> Option<usize> {
+    match c.to_ascii_uppercase() {
+        b'T' | b'U' => Some(0),
+        b'C' => Some(1),
+        b'A' => Some(2),
+        b'G' => Some(3),
+        _ => None,
+    }
+}
+
+/// index of a codon in the genetic code tables
+///
+fn codon_index(codon: &[u8]) -> Option<usize> {
+    if codon.len() != 3 { return None; }
+    let mut index = 0;
+    for &c in codon {
+        index = index * 4 + base_index(c)?;
+    }
+    Some(index)
+}
+
+impl GeneticCode {
+    /// the standard genetic code (NCBI table 1)
+    ///
+    pub fn standard() -> &'static GeneticCode {
+        &GENETIC_CODES[0]
+    }
+
+    /// look up a genetic code by its NCBI table ID
+    ///
+    pub fn from_id(id: u8) -> Result<&'static GeneticCode> {
+        GENETIC_CODES.iter()
+            .find(|x| x.id == id)
+            .ok_or(Error::UnknownGeneticCode(id))
+    }
+
+    /// all supported genetic codes
+    ///
+    pub fn all() -> &'static [GeneticCode] {
+        &GENETIC_CODES
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// amino acid for a DNA or RNA codon, '*' for stop codons,
+    /// None if the codon has other characters
+    ///
+    pub fn translate_codon(&self, codon: &[u8]) -> Option<u8> {
+        codon_index(codon).map(|i| self.amino_acids[i])
+    }
+
+    pub fn is_stop(&self, codon: &[u8]) -> bool {
+        self.translate_codon(codon) == Some(b'*')
+    }
+
+    /// codons that can start translation, including alternative
+    /// start codons
+    ///
+    pub fn is_start(&self, codon: &[u8]) -> bool {
+        codon_index(codon).is_some_and(|i| self.starts[i] == b'M')
+    }
+}
+
+/// what to do with stop codons during translation
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopCodons {
+    /// write stop codons as '*' and keep translating
+    Include,
+    /// end the protein at the first stop codon
+    Truncate,
+}
+
+/// translate DNA or RNA into protein, an incomplete codon at the
+/// end of the sequence is ignored
+///
+pub fn translate(seq: &str, code: &GeneticCode, stops: StopCodons) -> Result<String> {
+    let seq = seq.as_bytes();
+    let mut protein = String::with_capacity(seq.len() / 3);
+
+    for (i, codon) in seq.chunks(3).enumerate() {
+        if codon.len() < 3 { break; }
+        let amino_acid = match code.translate_codon(codon) {
+            Some(x) => x,
+            None => {
+                let j = codon.iter().position(|&c| base_index(c).is_none()).unwrap_or(0);
+                return Err(Error::InvalidCharacter(codon[j] as char, 3 * i + j));
+            },
+        };
+        if amino_acid == b'*' && stops == StopCodons::Truncate { break; }
+        protein.push(amino_acid as char);
+    }
+    Ok(protein)
+}
+
+/// strand of a DNA sequence a feature was found on
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+/// a genome substring whose translation is a given peptide, start
+/// is the 0-based position of the substring on the forward strand
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeptideEncoding {
+    pub start: usize,
+    pub strand: Strand,
+    pub dna: String,
+}
+
+/// find every substring of a genome, on either strand, that
+/// translates to a peptide
+///
+pub fn peptide_encodings(dna: &str, peptide: &str, code: &GeneticCode) -> Result<Vec<PeptideEncoding>> {
+    let n = dna.len();
+    let k = 3 * peptide.len();
+    check_k(k, n)?;
+    let reverse = revcomp(dna)?;
+    let mut res = Vec::new();
+
+    // translate each frame once, then slide the peptide along
+    // the translated frame
+    for &(strand, seq) in &[(Strand::Forward, dna), (Strand::Reverse, reverse.as_str())] {
+        for frame in 0..3 {
+            let protein = translate(&seq[frame..], code, StopCodons::Include)?;
+            let protein = protein.as_bytes();
+            if protein.len() < peptide.len() { continue; }
+
+            for i in 0..protein.len()-peptide.len()+1 {
+                if &protein[i..i+peptide.len()] != peptide.as_bytes() { continue; }
+                let position = frame + 3 * i;
+                let start = match strand {
+                    Strand::Forward => position,
+                    Strand::Reverse => n - position - k,
+                };
+                res.push(PeptideEncoding { start, strand, dna: dna[start..start+k].to_string() });
+            }
+        }
+    }
+    res.sort_by_key(|x| (x.start, x.strand));
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GeneticCode, StopCodons};
+
+    #[test]
+    fn translate() {
+        let rna = "AUGGCCAUGGCGCCCAGAACUGAGAUCAAUAGUACCCGUAUUAACGGGUGA";
+        let code = GeneticCode::standard();
+        let protein = super::translate(rna, code, StopCodons::Truncate).unwrap();
+        assert_eq!(protein, "MAMAPRTEINSTRING");
+        let protein = super::translate(rna, code, StopCodons::Include).unwrap();
+        assert_eq!(protein, "MAMAPRTEINSTRING*");
+
+        // DNA input, stop codon in the middle, incomplete trailing codon
+        let protein = super::translate("atgTAAgggTG", code, StopCodons::Include).unwrap();
+        assert_eq!(protein, "M*G");
+        assert!(super::translate("ATGNNN", code, StopCodons::Include).is_err());
+    }
+
+    #[test]
+    fn genetic_codes() {
+        let code = GeneticCode::from_id(2).unwrap();
+        assert_eq!(code.name(), "Vertebrate Mitochondrial");
+        assert_eq!(code.translate_codon(b"TGA"), Some(b'W'));
+        assert!(code.is_stop(b"AGA"));
+        assert!(code.is_start(b"ATA"));
+        assert!(GeneticCode::standard().is_stop(b"UGA"));
+        assert!(GeneticCode::standard().is_start(b"TTG"));
+        assert!(!GeneticCode::standard().is_start(b"ATA"));
+        assert!(GeneticCode::from_id(7).is_err());
+
+        for code in GeneticCode::all() {
+            assert!(code.amino_acids.iter().all(|c| b"ACDEFGHIKLMNPQRSTVWY*".contains(c)));
+            assert_eq!(GeneticCode::from_id(code.id()).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn peptide_encodings() {
+        use super::Strand;
+        let dna = "ATGGCCATGGCCCCCAGAACTGAGATCAATAGTACCCGTATTAACGGGTGA";
+        let res = super::peptide_encodings(dna, "MA", GeneticCode::standard()).unwrap();
+        let found: Vec<_> = res.iter().map(|x| (x.start, x.strand, x.dna.as_str())).collect();
+        assert_eq!(found, vec![(0, Strand::Forward, "ATGGCC"),
+                               (2, Strand::Reverse, "GGCCAT"),
+                               (6, Strand::Forward, "ATGGCC")]);
+        assert!(super::peptide_encodings("ATG", "MA", GeneticCode::standard()).is_err());
+    }
+}
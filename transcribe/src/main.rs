@@ -2,20 +2,18 @@ extern crate rosalind_lib;
 
 use std::env;
 
-use std::fs::File;
 use std::io::Read;
 use rosalind_lib::dna_utils::transcribe;
+use rosalind_lib::io::open_file;
 
 /// Read problem input from file
 ///
 pub fn read_input(filename: &str) -> String {
-    let mut fhandle = File::open(filename)
-        .ok()
+    let mut fhandle = open_file(filename)
         .expect("Could not open file");
 
     let mut dna = String::new();
     fhandle.read_to_string(&mut dna)
-        .ok()
         .expect("Unable to read");
 
     dna.trim().to_string()
@@ -26,7 +24,8 @@ fn main() {
         .expect("Need one argument");
 
     let string = read_input(&filename);
-    let res = transcribe(&string);
+    let res = transcribe(&string)
+        .expect("Could not transcribe");
     println!("{}", res);
 }
 
@@ -0,0 +1,47 @@
+extern crate rosalind_lib;
+
+use std::env;
+
+use std::io::Read;
+use rosalind_lib::io::open_file;
+use rosalind_lib::translation::{translate, GeneticCode, StopCodons};
+
+/// Read problem input from file
+///
+pub fn read_input(filename: &str) -> String {
+    let mut fhandle = open_file(filename)
+        .expect("Could not open file");
+
+    let mut rna = String::new();
+    fhandle.read_to_string(&mut rna)
+        .expect("Unable to read");
+
+    rna.trim().to_string()
+}
+
+fn main() {
+    let filename = env::args().nth(1)
+        .expect("Need at least one argument");
+
+    // optional second argument selects an NCBI genetic code
+    let table_id = env::args().nth(2)
+        .map_or(1, |x| x.parse().expect("Could not parse genetic code table id"));
+    let code = GeneticCode::from_id(table_id)
+        .expect("Unknown genetic code");
+
+    let string = read_input(&filename);
+    let res = translate(&string, code, StopCodons::Truncate)
+        .expect("Could not translate");
+    println!("{}", res);
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn read_input() {
+        let filename = "test.txt";
+        let rna = "AUGGCCAUGGCGCCCAGAACUGAGAUCAAUAGUACCCGUAUUAACGGGUGA";
+        let res = super::read_input(filename);
+        assert_eq!(res, rna);
+    }
+}